@@ -1,18 +1,242 @@
 //! Definition of CLI commands/sub commands + its option parameters
+use dta4hana::credential_store::StoreKind;
+use dta4hana::dta_app::{
+    parse_max_runtime, CredentialFormat, ExportFormat, OlderThan, UnknownSource,
+};
+use dta4hana::rate_limiter::{parse_quota, Endpoint};
+use dta4hana::twitter_client::ApiVersion;
+use regex::Regex;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 use structopt::StructOpt;
 
+/// Options of `delete` which need the fetched timeline, they can't be used with `--from-stdin` and `--from-csv`
+/// The given ids are deleted one by one without the tweets, so these filters, the period and the workers would be ignored silently
+const TIMELINE_ONLY_ARGS: &[&str] = &[
+    "since",
+    "until",
+    "older-than",
+    "since-id",
+    "until-id",
+    "exclude-media",
+    "media-only",
+    "exclude-replies",
+    "replies-only",
+    "retweets-only",
+    "retweets-of",
+    "keep-recent",
+    "keep-likes",
+    "keep-retweets",
+    "protect-engaged",
+    "smart-purge",
+    "from-source",
+    "not-from-source",
+    "unknown-source",
+    "lang",
+    "not-lang",
+    "delete-und",
+    "only-sensitive",
+    "exclude-withheld",
+    "contains",
+    "matches",
+    "download-media",
+    "concurrency",
+];
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "DTA4HANA", about = "Delete them all for HANA")]
 pub struct CommandLineArgs {
     #[structopt(subcommand)]
     pub action: Action,
 
-    /// Use a different journal file.
+    /// Use a different journal file, DTA4HANA_CONFIG is used if it is not given.
     #[structopt(parse(from_os_str), short, long)]
     pub config_file: Option<PathBuf>,
+
+    /// Directory to write the work files e.g. fetched tweets, the directory of the config file by default.
+    #[structopt(parse(from_os_str), long)]
+    pub work_dir: Option<PathBuf>,
+
+    /// SQLite DB of the deletion history, delete/unlike skip the ids in it and record the new ones. No history by default.
+    #[structopt(parse(from_os_str), long)]
+    pub db: Option<PathBuf>,
+
+    /// Profile name of the credential in the config file, each profile can have a different account.
+    #[structopt(short, long, default_value = "default")]
+    pub profile: String,
+
+    /// Verbose logs, -v for debug and -vv for trace. `DTA4HANA_LOG_LEVEL` is prioritized if it is set.
+    #[structopt(short, long, parse(from_occurrences))]
+    pub verbose: u8,
+
+    /// Suppress the log per tweet e.g. "Deleted Id: ...", the summary, the warnings and the errors are still shown.
+    #[structopt(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Log output format, json emits one JSON object per line for automation.
+    #[structopt(long, default_value = "text", possible_values = &["text", "json"])]
+    pub log_format: LogFormat,
+
+    /// Bearer Token, it overrides `DTA4HANA_B`.
+    #[structopt(long)]
+    pub bearer: Option<String>,
+
+    /// Consumer Key, it overrides `DTA4HANA_CK`.
+    #[structopt(long)]
+    pub consumer_key: Option<String>,
+
+    /// Consumer Secret, it overrides `DTA4HANA_CS`.
+    #[structopt(long)]
+    pub consumer_secret: Option<String>,
+
+    /// Access Token of the user for a one-off run, the config file is neither read nor written. It is visible in the process list.
+    #[structopt(long, requires = "oauth-token-secret")]
+    pub oauth_token: Option<String>,
+
+    /// Access Token Secret of the user, see --oauth-token.
+    #[structopt(long, requires = "oauth-token")]
+    pub oauth_token_secret: Option<String>,
+
+    /// Encrypt the stored credential with this passphrase, existing plaintext credential will be encrypted.
+    #[structopt(long, env = "DTA4HANA_PASSPHRASE", hide_env_values = true)]
+    pub passphrase: Option<String>,
+
+    /// Where the credential is stored, keyring is the OS keychain e.g. macOS Keychain and Secret Service.
+    /// It falls back to the config file with a warning if the keychain is not available.
+    #[structopt(long, default_value = "file", possible_values = &["file", "keyring"])]
+    pub store: StoreKind,
+
+    /// Proxy URL e.g. http://proxy.example.com:8080 or socks5://127.0.0.1:1080.
+    #[structopt(long, env = "HTTPS_PROXY")]
+    pub proxy: Option<String>,
+
+    /// Read/write timeout of each request in seconds, 0 means no timeout.
+    #[structopt(long, default_value = "5")]
+    pub timeout_secs: u64,
+
+    /// Connect timeout of each request in seconds, 0 means no timeout.
+    #[structopt(long, default_value = "5")]
+    pub connect_timeout_secs: u64,
+
+    /// How many times each request including the login is retried on the network errors e.g. connection reset and 503, 0 means no retry.
+    #[structopt(long, default_value = "3")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry in milliseconds, it is doubled on each retry.
+    #[structopt(long, default_value = "500")]
+    pub retry_base_delay_ms: u64,
+
+    /// Upper limit of the delay before each retry in milliseconds.
+    #[structopt(long, default_value = "30000")]
+    pub retry_max_delay_ms: u64,
+
+    /// Random 0..N milliseconds added to the delay before each retry, 0 means no jitter.
+    #[structopt(long, default_value = "250")]
+    pub retry_jitter_ms: u64,
+
+    /// Requests per 15 minutes of an endpoint e.g. delete_tweet=30, it overrides the known limit of Twitter.
+    /// 0 means no limit. It can be repeated for the endpoints.
+    #[structopt(long = "rate-limit", number_of_values = 1, parse(try_from_str = parse_quota))]
+    pub rate_limits: Vec<(Endpoint, u32)>,
+
+    /// API version of the tweet deletion, v2 requires `login --oauth2`.
+    /// It is v1 by default, the OAuth 2.0 access token is not refreshed yet.
+    #[structopt(long, possible_values = &["v1", "v2"])]
+    pub api_version: Option<ApiVersion>,
+
+    /// User-Agent of the requests, it is dta4hana/<version> by default.
+    #[structopt(long)]
+    pub user_agent: Option<String>,
+
+    /// Random 0..N milliseconds added to the interval of each delete/unlike request, 0 means no jitter.
+    #[structopt(long, default_value = "0")]
+    pub jitter_ms: u64,
+
+    /// Wait N milliseconds before each next page of the timeline and the likes, it is separate from the interval of the deletion.
+    #[structopt(long, default_value = "500")]
+    pub page_interval_ms: u64,
+
+    /// Stop delete and unlike cleanly after this wall-clock time e.g. 90s, 30m or 2h, the summary shows the partial progress.
+    #[structopt(long, parse(try_from_str = parse_max_runtime))]
+    pub max_runtime: Option<Duration>,
+
+    /// Before the action, poll the API until it is available instead of failing during an outage.
+    #[structopt(long)]
+    pub wait_until_available: bool,
+
+    /// How long --wait-until-available polls the API e.g. 90s, 30m or 2h, it is 10m by default.
+    #[structopt(long, requires = "wait-until-available", parse(try_from_str = parse_max_runtime))]
+    pub wait_timeout: Option<Duration>,
+
+    /// Tweets per page of the timeline and the likes between 5 and 100, the likes are at least 10.
+    #[structopt(long, default_value = "100")]
+    pub page_size: u8,
+
+    /// Comma separated tweet.fields instead of the default e.g. created_at,public_metrics,lang, created_at and the fields read by the delete filters are always added.
+    #[structopt(long)]
+    pub fields: Option<String>,
+
+    /// Comma separated expansions of the tweets e.g. attachments.media_keys, no expansion by default.
+    #[structopt(long)]
+    pub expansions: Option<String>,
+
+    /// Username of the timeline for fetch and count instead of you, the destroy actions accept only yourself.
+    #[structopt(long)]
+    pub user: Option<String>,
+
+    /// Skip the confirmation before the destructive actions, it is required if stdin is not a terminal.
+    #[structopt(short, long)]
+    pub yes: bool,
+
+    /// Disable the progress bar and log each tweet instead, it is disabled anyway if stderr is not a terminal.
+    #[structopt(long)]
+    pub no_progress: bool,
+
+    /// Print the summary of the destroy actions as a JSON object to stdout, the logs are always written to stderr.
+    #[structopt(long)]
+    pub summary_json: bool,
 }
 
+/// Format of the results printed to stdout e.g. `config-show`
+#[derive(Debug)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(anyhow::anyhow!("Unknown output format: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(anyhow::anyhow!("Unknown log format: {}", s)),
+        }
+    }
+}
+
+// Deleteのオプションが多いが, 一度しか生成しないためBoxにしない
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, StructOpt)]
 pub enum Action {
     #[structopt(about = "Delete your tweets")]
@@ -30,9 +254,398 @@ pub enum Action {
             help = "The most latest date for the action e.g. 2022-12-31"
         )]
         until: Option<String>,
+
+        #[structopt(
+            long,
+            alias = "min-age",
+            conflicts_with = "until",
+            help = "Delete only the tweets older than this age from today e.g. 90d, 2w, 6mo, 1y"
+        )]
+        older_than: Option<OlderThan>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["since", "until", "older-than"],
+            help = "The oldest tweet id for the action, inclusive. It can't be used with the dates"
+        )]
+        since_id: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["since", "until", "older-than"],
+            help = "The newest tweet id for the action, inclusive. It can't be used with the dates"
+        )]
+        until_id: Option<String>,
+
+        #[structopt(
+            long,
+            help = "How many delete requests can be sent at the same time, the request interval is kept in total [default: 1]"
+        )]
+        concurrency: Option<usize>,
+
+        #[structopt(
+            long,
+            conflicts_with = "media-only",
+            help = "Keep the tweets which have photos/videos"
+        )]
+        exclude_media: bool,
+
+        #[structopt(long, help = "Delete only the tweets which have photos/videos")]
+        media_only: bool,
+
+        #[structopt(
+            long,
+            conflicts_with = "replies-only",
+            help = "Keep the replies(including your threads)"
+        )]
+        exclude_replies: bool,
+
+        #[structopt(
+            long,
+            help = "Delete only the replies, a tweet which quotes and replies at the same time is treated as a reply"
+        )]
+        replies_only: bool,
+
+        #[structopt(
+            long,
+            conflicts_with = "replies-only",
+            help = "Delete(i.e. unretweet) only the retweets"
+        )]
+        retweets_only: bool,
+
+        #[structopt(
+            long,
+            requires = "retweets-only",
+            help = "Unretweet only the retweets of this user's tweets, it looks up the user and expands the retweeted tweets"
+        )]
+        retweets_of: Option<String>,
+
+        #[structopt(long, help = "Delete your pinned tweet as well, it is kept by default")]
+        include_pinned: bool,
+
+        #[structopt(long, help = "The maximum number of tweets to delete in this run")]
+        max: Option<usize>,
+
+        #[structopt(
+            long,
+            help = "Keep the popular tweets which have this number of likes or more"
+        )]
+        keep_likes: Option<u32>,
+
+        #[structopt(
+            long,
+            help = "Keep the popular tweets which have this number of retweets or more"
+        )]
+        keep_retweets: Option<u32>,
+
+        #[structopt(
+            long,
+            help = "Keep the tweets which the others engaged with, i.e. the replies, retweets and quotes are more than this in total"
+        )]
+        protect_engaged: Option<u64>,
+
+        #[structopt(
+            long,
+            requires = "older-than",
+            help = "Delete the tweets older than --older-than unless they are popular, the thresholds are 10 likes and 3 retweets unless --keep-likes/--keep-retweets are given"
+        )]
+        smart_purge: bool,
+
+        #[structopt(
+            long,
+            default_value = "0",
+            help = "Keep the latest N tweets, they are counted in the tweets filtered by the other options e.g. --since"
+        )]
+        keep_recent: usize,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["from-stdin", "from-csv"],
+            help = "Delete the tweets of each fetched page in random order instead of the newest first, the targets are not changed"
+        )]
+        shuffle: bool,
+
+        #[structopt(
+            long,
+            requires = "shuffle",
+            help = "Seed of --shuffle for the reproducible order"
+        )]
+        seed: Option<u64>,
+
+        #[structopt(
+            long,
+            help = "Delete only the tweets which contain this text, it is matched against the text returned by the API(retweets may be truncated)"
+        )]
+        contains: Option<String>,
+
+        #[structopt(
+            long,
+            help = "Delete only the tweets posted via the client app whose name contains this, case-insensitive e.g. \"Twitter for iPhone\""
+        )]
+        from_source: Option<String>,
+
+        #[structopt(
+            long,
+            help = "Keep the tweets posted via the client app whose name contains this, case-insensitive"
+        )]
+        not_from_source: Option<String>,
+
+        #[structopt(
+            long,
+            default_value = "keep",
+            possible_values = &["keep", "delete"],
+            help = "How to handle the tweets without the source in --from-source and --not-from-source, the API doesn't always return it"
+        )]
+        unknown_source: UnknownSource,
+
+        #[structopt(
+            long,
+            help = "Delete only the tweets in this language, the exact BCP 47 code detected by Twitter e.g. ja"
+        )]
+        lang: Option<String>,
+
+        #[structopt(
+            long,
+            help = "Keep the tweets in this language, the exact BCP 47 code detected by Twitter e.g. en"
+        )]
+        not_lang: Option<String>,
+
+        #[structopt(
+            long,
+            help = "Delete the tweets whose language is und(undetermined) in --lang and --not-lang, they are kept by default"
+        )]
+        delete_und: bool,
+
+        #[structopt(
+            long,
+            help = "Delete only the tweets flagged as possibly_sensitive, the tweets without the field are kept"
+        )]
+        only_sensitive: bool,
+
+        #[structopt(long, help = "Keep the tweets withheld in any country or by copyright")]
+        exclude_withheld: bool,
+
+        #[structopt(
+            long,
+            parse(try_from_str = Regex::new),
+            help = "Delete only the tweets which match this regular expression, it is matched against the text returned by the API(retweets may be truncated)"
+        )]
+        matches: Option<Regex>,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with_all = &["no-backup", "output-dir"],
+            help = "Append the tweets to this JSONL file before deleting them instead of the automatic backup"
+        )]
+        backup: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            help = "Don't back up the tweets automatically before deleting them"
+        )]
+        no_backup: bool,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with = "no-backup",
+            help = "Directory of the automatic backup, each run writes a JSONL file named by the time. Default is backups in the work directory"
+        )]
+        output_dir: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Download the photos/videos into this directory before deleting the tweets, the tweet is kept if its download fails. Nothing is downloaded in --dry-run"
+        )]
+        download_media: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            help = "Show the tweets to be deleted without deleting them, they are backed up if --backup is given"
+        )]
+        dry_run: bool,
+
+        #[structopt(
+            long,
+            help = "Stop at the first failed deletion, the failed tweets are skipped and the rest are continued by default"
+        )]
+        fail_fast: bool,
+
+        #[structopt(
+            long,
+            conflicts_with = "fail-fast",
+            help = "Stop if the failed deletions are more than N e.g. the credential is revoked [default: 10]"
+        )]
+        max_failures: Option<usize>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = TIMELINE_ONLY_ARGS,
+            help = "Delete the tweet ids separated by whitespace/newline from stdin without fetching the timeline, --yes is required"
+        )]
+        from_stdin: bool,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with = "from-stdin",
+            conflicts_with_all = TIMELINE_ONLY_ARGS,
+            help = "Delete the tweet ids of the id column of the CSV e.g. curated in a spreadsheet without fetching the timeline"
+        )]
+        from_csv: Option<PathBuf>,
+    },
+    #[structopt(about = "Delete your direct messages, it requires `login --oauth2`")]
+    DeleteDms {
+        #[structopt(
+            short,
+            long,
+            help = "The most earliest date for the action e.g. 2022-01-01"
+        )]
+        since: Option<String>,
+
+        #[structopt(
+            short,
+            long,
+            help = "The most latest date for the action e.g. 2022-12-31"
+        )]
+        until: Option<String>,
+    },
+    #[structopt(about = "Delete your scheduled tweets, it requires the access to Twitter Ads API")]
+    DeleteScheduled {
+        #[structopt(
+            long,
+            help = "Ads account id which has the scheduled tweets, it is shown on ads.twitter.com"
+        )]
+        account_id: String,
+
+        #[structopt(long, help = "List the scheduled tweets without deleting them")]
+        dry_run: bool,
+    },
+    #[structopt(
+        about = "Write a template config file to fill in your credential, it is not needed if you login"
+    )]
+    ConfigInit {
+        #[structopt(long, help = "Overwrite the existing config file")]
+        force: bool,
+    },
+    #[structopt(
+        about = "Show the resolved config e.g. the config file, timeouts and where the credentials come from, the secrets are masked"
+    )]
+    ConfigShow {
+        #[structopt(
+            long,
+            default_value = "text",
+            possible_values = &["text", "json"],
+            help = "json prints the entries as a JSON array"
+        )]
+        format: OutputFormat,
+    },
+    #[structopt(
+        about = "Count your tweets and sum up their metrics without deleting them, Ctrl-C prints next_token to resume"
+    )]
+    Count {
+        #[structopt(
+            short,
+            long,
+            help = "The most earliest date for the action e.g. 2022-01-01"
+        )]
+        since: Option<String>,
+
+        #[structopt(
+            short,
+            long,
+            help = "The most latest date for the action e.g. 2022-12-31"
+        )]
+        until: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["since", "until"],
+            help = "The oldest tweet id for the action, inclusive. It can't be used with the dates"
+        )]
+        since_id: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["since", "until"],
+            help = "The newest tweet id for the action, inclusive. It can't be used with the dates"
+        )]
+        until_id: Option<String>,
+
+        #[structopt(
+            long,
+            help = "Start from the page of this next_token printed by the previous run, it expires on the server side"
+        )]
+        start_token: Option<String>,
+    },
+    #[structopt(
+        about = "Report your timeline without deleting it e.g. the date range, the metrics and the most engaged tweets"
+    )]
+    Report {
+        #[structopt(
+            short,
+            long,
+            help = "The most earliest date for the action e.g. 2022-01-01"
+        )]
+        since: Option<String>,
+
+        #[structopt(
+            short,
+            long,
+            help = "The most latest date for the action e.g. 2022-12-31"
+        )]
+        until: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["since", "until"],
+            help = "The oldest tweet id for the action, inclusive. It can't be used with the dates"
+        )]
+        since_id: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["since", "until"],
+            help = "The newest tweet id for the action, inclusive. It can't be used with the dates"
+        )]
+        until_id: Option<String>,
+
+        #[structopt(
+            long,
+            help = "Start from the page of this next_token printed by the previous run, it expires on the server side"
+        )]
+        start_token: Option<String>,
+
+        #[structopt(
+            long,
+            default_value = "text",
+            possible_values = &["text", "json"],
+            help = "json prints the report as a JSON object"
+        )]
+        format: OutputFormat,
+    },
+    #[structopt(
+        about = "Show the remaining requests and the reset time of the timeline, the deletion and the likes e.g. before a big run"
+    )]
+    RateLimit {
+        #[structopt(
+            long,
+            default_value = "text",
+            possible_values = &["text", "json"],
+            help = "json prints the statuses as a JSON array, the reset is in UNIX epoch seconds"
+        )]
+        format: OutputFormat,
     },
     #[structopt(
-        about = "Fetch your tweets, this is for the test purpose(pull the tweets and save it in your local)"
+        about = "Check the app credentials, the config file and the connectivity to the APIs without login"
+    )]
+    Doctor,
+    #[structopt(about = "Show the cumulative counts of the deletion history in --db")]
+    History,
+    #[structopt(
+        about = "Export your timeline, all the tweets in the period are saved in your local, Ctrl-C prints next_token to resume"
     )]
     Fetch {
         #[structopt(
@@ -48,9 +661,161 @@ pub enum Action {
             help = "The most latest date for the action e.g. 2022-12-31"
         )]
         until: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["since", "until"],
+            help = "The oldest tweet id for the action, inclusive. It can't be used with the dates"
+        )]
+        since_id: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["since", "until"],
+            help = "The newest tweet id for the action, inclusive. It can't be used with the dates"
+        )]
+        until_id: Option<String>,
+
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "Path of the exported file, the work file in the work directory is used if it is omitted. - writes to stdout"
+        )]
+        output: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            conflicts_with = "output",
+            help = "File name of the export in the work directory with {username}, {date} and {action} e.g. {username}-{date}.jsonl"
+        )]
+        output_template: Option<String>,
+
+        #[structopt(
+            long,
+            default_value = "json",
+            possible_values = &["json", "jsonl", "csv"],
+            help = "json writes a JSON array, jsonl writes one tweet per line, csv writes the main fields with the header"
+        )]
+        format: ExportFormat,
+
+        #[structopt(
+            long,
+            help = "Start from the page of this next_token printed by the previous run, it expires on the server side"
+        )]
+        start_token: Option<String>,
     },
     #[structopt(about = "Login and overwrite existing credential")]
-    Login,
+    Login {
+        #[structopt(
+            long,
+            help = "Login with OAuth 2.0 additionally, it is required for some actions e.g. unbookmark"
+        )]
+        oauth2: bool,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["oauth2", "pin"],
+            help = "Start the login without the prompts, the authorize URL is printed and the PIN is given by --pin in the next run"
+        )]
+        username: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with = "oauth2",
+            help = "Complete the login started by --username with the PIN shown on your browser"
+        )]
+        pin: Option<String>,
+
+        #[structopt(
+            long,
+            default_value = "none",
+            possible_values = &["none", "json"],
+            help = "json prints the username and the id of the logged in account to stdout, the tokens are never printed"
+        )]
+        print_credential: CredentialFormat,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["username", "pin"],
+            help = "Open the authorize URL in your browser, the URL is printed if no browser is available e.g. SSH"
+        )]
+        open: bool,
+
+        #[structopt(
+            long,
+            requires = "oauth2",
+            help = "OAuth 2.0 scopes separated by space or comma instead of all the scopes of the actions e.g. \"tweet.read tweet.write users.read offline.access\""
+        )]
+        scopes: Option<String>,
+    },
+    #[structopt(about = "Unbookmark your bookmarked tweets, it requires `login --oauth2`")]
+    Unbookmark,
     #[structopt(about = "Unlike your liked tweets from the latest to the older")]
-    Unlike,
+    Unlike {
+        #[structopt(long, help = "The maximum number of tweets to unlike in this run")]
+        max: Option<usize>,
+
+        #[structopt(
+            long,
+            help = "Unlike only the tweets created before this age from today e.g. 90d, 2w, 6mo, 1y"
+        )]
+        older_than: Option<OlderThan>,
+
+        #[structopt(
+            long,
+            conflicts_with = "older-than",
+            help = "Unlike only the tweets created before this date e.g. 2022-12-31, it is the date of the tweet, not when you liked it"
+        )]
+        until: Option<String>,
+    },
+    #[structopt(
+        about = "Delete your tweets and then unlike your liked tweets in one run, e.g. a full cleanse of the account"
+    )]
+    Purge {
+        #[structopt(
+            short,
+            long,
+            help = "The most earliest date of the tweets to delete e.g. 2022-01-01, it is not applied to the likes"
+        )]
+        since: Option<String>,
+
+        #[structopt(
+            long,
+            help = "Delete and unlike only the tweets created before this age from today e.g. 90d, 2w, 6mo, 1y"
+        )]
+        older_than: Option<OlderThan>,
+
+        #[structopt(
+            short,
+            long,
+            conflicts_with = "older-than",
+            help = "The most latest date for the action e.g. 2022-12-31, the likes are unliked if the tweets were created before it"
+        )]
+        until: Option<String>,
+
+        #[structopt(
+            long,
+            help = "Don't back up the tweets automatically before deleting them"
+        )]
+        no_backup: bool,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with = "no-backup",
+            help = "Directory of the automatic backup, each run writes a JSONL file named by the time. Default is backups in the work directory"
+        )]
+        output_dir: Option<PathBuf>,
+    },
+    #[structopt(
+        about = "Check the tweets in the file were actually deleted, the remaining ids are printed to stdout"
+    )]
+    Verify {
+        #[structopt(
+            parse(from_os_str),
+            help = "File of the deleted tweets e.g. the backup of `delete --backup`, the export of `fetch` or the ids separated by whitespace/newline"
+        )]
+        file: PathBuf,
+    },
 }
@@ -0,0 +1,312 @@
+//! Storage backends of the user credential, see [`crate::dta_app::init_client()`] and [`crate::dta_app::login()`]
+//! The login and init flow only loads and saves through [`CredentialStore`], so it doesn't depend on the file
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use log::warn;
+
+use crate::dta_app::{
+    find_app_user_credential, load_app_user_credential, store_app_user_credential,
+};
+use crate::twitter_client::TwitterAppUserCredential;
+
+/// Where the user credential is stored
+pub trait CredentialStore {
+    /// Load the stored user credential, it fails if there is no credential
+    fn load(&self) -> Result<TwitterAppUserCredential>;
+
+    /// Load the stored user credential, None if there is no credential e.g. before login
+    /// It fails only if the store can't be read and must not be overwritten by the login
+    fn find(&self) -> Result<Option<TwitterAppUserCredential>>;
+
+    /// Store the user credential, the stored one is replaced
+    /// * user_cred: user credential to be stored
+    fn save(&self, user_cred: &TwitterAppUserCredential) -> Result<()>;
+
+    /// Which credential it is for the messages, e.g. `the profile "default"`
+    fn describe(&self) -> String;
+}
+
+/// [`CredentialStore`] of a profile in the config file
+/// The file is JSON or TOML by the extension, and it is encrypted if the passphrase is given
+pub struct FileStore {
+    config_path: PathBuf,
+    profile: String,
+    passphrase: Option<String>,
+}
+
+impl FileStore {
+    /// Constructs new store
+    /// * config_path: path to the user credential file
+    /// * profile: profile name of the user credential, other profiles in the file are kept
+    /// * passphrase: if it is given, the user credential file is encrypted with it
+    pub fn new(config_path: &Path, profile: &str, passphrase: Option<&str>) -> Self {
+        FileStore {
+            config_path: config_path.to_path_buf(),
+            profile: profile.to_string(),
+            passphrase: passphrase.map(str::to_string),
+        }
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn load(&self) -> Result<TwitterAppUserCredential> {
+        load_app_user_credential(&self.config_path, &self.profile, self.passphrase.as_deref())
+    }
+
+    fn find(&self) -> Result<Option<TwitterAppUserCredential>> {
+        find_app_user_credential(&self.config_path, &self.profile, self.passphrase.as_deref())
+    }
+
+    fn save(&self, user_cred: &TwitterAppUserCredential) -> Result<()> {
+        store_app_user_credential(
+            user_cred,
+            &self.config_path,
+            &self.profile,
+            self.passphrase.as_deref(),
+        )
+    }
+
+    fn describe(&self) -> String {
+        format!("the profile {:?}", self.profile)
+    }
+}
+
+/// Service name of the entries in the OS keychain, each entry is named by the profile
+pub const KEYRING_SERVICE: &str = "dta4hana";
+
+/// [`CredentialStore`] of a profile in the OS keychain e.g. macOS Keychain, Windows Credential Manager and Secret Service
+/// The user credential is stored as JSON in the password of the entry, so the tokens are not left in the file
+pub struct KeyringStore {
+    entry: keyring::Entry,
+    profile: String,
+}
+
+impl KeyringStore {
+    /// Open the entry of the profile
+    /// It fails if the keychain is not available e.g. headless Linux without Secret Service
+    /// * profile: profile name of the user credential, it is the name of the entry
+    pub fn open(profile: &str) -> Result<Self> {
+        let entry = match keyring::Entry::new(KEYRING_SERVICE, profile) {
+            Ok(entry) => entry,
+            Err(e) => return Err(anyhow::anyhow!("Keyring is not available: {}", e)),
+        };
+        let store = KeyringStore {
+            entry,
+            profile: profile.to_string(),
+        };
+        // エントリがなくてもキーチェーン自体に接続できるかを確かめる
+        store.find()?;
+        Ok(store)
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn load(&self) -> Result<TwitterAppUserCredential> {
+        match self.find()? {
+            Some(user_cred) => Ok(user_cred),
+            None => Err(anyhow::anyhow!(
+                "No credential is found in the keyring for the profile {:?}, please login first.",
+                self.profile
+            )),
+        }
+    }
+
+    fn find(&self) -> Result<Option<TwitterAppUserCredential>> {
+        match self.entry.get_password() {
+            Ok(password) => Ok(Some(serde_json::from_str(&password)?)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("Keyring is not available: {}", e)),
+        }
+    }
+
+    fn save(&self, user_cred: &TwitterAppUserCredential) -> Result<()> {
+        match self.entry.set_password(&serde_json::to_string(user_cred)?) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(anyhow::anyhow!(
+                "Failed to store the credential in the keyring: {}",
+                e
+            )),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("the profile {:?} in the keyring", self.profile)
+    }
+}
+
+/// Backend of the user credential for `--store`
+/// * File: [`FileStore`]
+/// * Keyring: [`KeyringStore`], it falls back to [`FileStore`] if the keychain is not available
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoreKind {
+    File,
+    Keyring,
+}
+
+impl FromStr for StoreKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(StoreKind::File),
+            "keyring" => Ok(StoreKind::Keyring),
+            _ => Err(anyhow::anyhow!("Unknown credential store: {}", s)),
+        }
+    }
+}
+
+/// Open the store of the profile
+/// * kind: backend of the user credential
+/// * config_path: path to the user credential file, it is also used for the fallback of the keyring
+/// * profile: profile name of the user credential
+/// * passphrase: if it is given, the user credential file is encrypted with it, it is not used for the keyring
+pub fn open_store(
+    kind: StoreKind,
+    config_path: &Path,
+    profile: &str,
+    passphrase: Option<&str>,
+) -> Box<dyn CredentialStore> {
+    let file_store = FileStore::new(config_path, profile, passphrase);
+    match kind {
+        StoreKind::File => Box::new(file_store),
+        StoreKind::Keyring => keyring_or_file(KeyringStore::open(profile), file_store),
+    }
+}
+
+/// The keyring if it is available, otherwise the file with a warning
+/// * keyring: result of [`KeyringStore::open()`]
+/// * file_store: fallback of the keyring
+fn keyring_or_file(
+    keyring: Result<KeyringStore>,
+    file_store: FileStore,
+) -> Box<dyn CredentialStore> {
+    match keyring {
+        Ok(keyring) => Box::new(keyring),
+        Err(e) => {
+            warn!(
+                "{} The credential is stored in the file {:?} instead.",
+                e, file_store.config_path
+            );
+            Box::new(file_store)
+        }
+    }
+}
+
+/// [`CredentialStore`] in memory, nothing is left after the run e.g. for the tests and the embedding tools
+#[derive(Default)]
+pub struct MemoryStore {
+    user_cred: Mutex<Option<TwitterAppUserCredential>>,
+}
+
+impl MemoryStore {
+    /// Constructs new store
+    /// * user_cred: stored user credential, None is the same as before login
+    pub fn new(user_cred: Option<TwitterAppUserCredential>) -> Self {
+        MemoryStore {
+            user_cred: Mutex::new(user_cred),
+        }
+    }
+}
+
+impl CredentialStore for MemoryStore {
+    fn load(&self) -> Result<TwitterAppUserCredential> {
+        match self.find()? {
+            Some(user_cred) => Ok(user_cred),
+            None => Err(anyhow::anyhow!(
+                "No credential is found in the memory, please login first."
+            )),
+        }
+    }
+
+    fn find(&self) -> Result<Option<TwitterAppUserCredential>> {
+        Ok(self.user_cred.lock().unwrap().clone())
+    }
+
+    fn save(&self, user_cred: &TwitterAppUserCredential) -> Result<()> {
+        self.user_cred.lock().unwrap().replace(user_cred.clone());
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        String::from("the memory")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::credential_store::{
+        keyring_or_file, CredentialStore, FileStore, KeyringStore, MemoryStore, StoreKind,
+    };
+    use crate::twitter_client::TwitterAppUserCredential;
+
+    fn user_cred(id: &str) -> TwitterAppUserCredential {
+        TwitterAppUserCredential {
+            username: "user".to_string(),
+            id: id.to_string(),
+            oauth_token: "token".to_string(),
+            oauth_token_secret: "secret".to_string(),
+            oauth2: None,
+        }
+    }
+
+    /// Save and load through the trait, the store must be empty at first
+    fn save_and_load(store: &dyn CredentialStore) {
+        assert!(store.find().unwrap().is_none());
+        assert!(store.load().is_err());
+
+        store.save(&user_cred("1")).unwrap();
+        store.save(&user_cred("2")).unwrap();
+        assert_eq!(store.load().unwrap().id, "2");
+        assert_eq!(store.find().unwrap().unwrap().id, "2");
+    }
+
+    #[test]
+    fn save_and_load_in_memory() {
+        save_and_load(&MemoryStore::default());
+        assert_eq!(
+            MemoryStore::new(Some(user_cred("1"))).load().unwrap().id,
+            "1"
+        );
+    }
+
+    #[test]
+    fn save_and_load_in_file() {
+        let mut config_path = std::env::temp_dir();
+        config_path.push(format!("dta4hana.test.{}.json", uuid::Uuid::new_v4()));
+        let store = FileStore::new(&config_path, "sub", Some("passphrase"));
+        save_and_load(&store);
+        assert_eq!(store.describe(), "the profile \"sub\"");
+
+        // 他のプロファイルは別の認証情報
+        let other = FileStore::new(&config_path, "other", Some("passphrase"));
+        assert!(other.find().unwrap().is_none());
+        // 暗号化されたファイルは, パスフレーズなしでは読めない
+        assert!(FileStore::new(&config_path, "sub", None).find().is_err());
+        std::fs::remove_file(&config_path).unwrap();
+    }
+
+    #[test]
+    fn save_and_load_in_keyring() {
+        // 実際のキーチェーンには書き込まない
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        let store = KeyringStore::open("sub").unwrap();
+        save_and_load(&store);
+        assert_eq!(store.describe(), "the profile \"sub\" in the keyring");
+    }
+
+    #[test]
+    fn fall_back_to_file_without_keyring() {
+        let config_path = std::env::temp_dir().join("config.json");
+        let store = keyring_or_file(
+            Err(anyhow::anyhow!("Keyring is not available")),
+            FileStore::new(&config_path, "sub", None),
+        );
+        assert_eq!(store.describe(), "the profile \"sub\"");
+
+        assert_eq!("keyring".parse::<StoreKind>().unwrap(), StoreKind::Keyring);
+        assert!("vault".parse::<StoreKind>().is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Encryption of the stored user credential
+//! The key is derived from the passphrase with Argon2, and the data is encrypted with AES-256-GCM
+//!
+//! Encrypted data layout: `MAGIC` + salt(16 bytes) + nonce(12 bytes) + ciphertext
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::Result;
+use argon2::Argon2;
+
+/// Header for detecting the encrypted file, plaintext JSON never starts with this
+const MAGIC: &[u8] = b"DTA4HANA-ENC1\n";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Whether the data is encrypted by [`encrypt()`]
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypt the data with the passphrase
+/// Salt and nonce are generated for each call
+pub fn encrypt(plain: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, &salt)?);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let encrypted = match cipher.encrypt(&nonce, plain) {
+        Ok(encrypted) => encrypted,
+        Err(_) => return Err(anyhow::anyhow!("Failed to encrypt the credential.")),
+    };
+
+    let mut data = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + encrypted.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&encrypted);
+    Ok(data)
+}
+
+/// Decrypt the data encrypted by [`encrypt()`]
+/// It returns an error if the passphrase is wrong or the data is broken
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if !is_encrypted(data) || data.len() < MAGIC.len() + SALT_LEN + NONCE_LEN {
+        return Err(anyhow::anyhow!(
+            "The credential is not encrypted or broken."
+        ));
+    }
+    let (salt, rest) = data[MAGIC.len()..].split_at(SALT_LEN);
+    let (nonce, encrypted) = rest.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, salt)?);
+    match cipher.decrypt(Nonce::from_slice(nonce), encrypted) {
+        Ok(plain) => Ok(plain),
+        Err(_) => Err(anyhow::anyhow!(
+            "Failed to decrypt the credential, the passphrase may be wrong."
+        )),
+    }
+}
+
+/// Derive 256 bits key from the passphrase
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>> {
+    let mut key = Key::<Aes256Gcm>::default();
+    match Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key) {
+        Ok(_) => Ok(key),
+        Err(e) => Err(anyhow::anyhow!("Failed to derive the key: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::{decrypt, encrypt, is_encrypted};
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let plain = br#"{"username":"user"}"#;
+        let encrypted = encrypt(plain, "passphrase").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!is_encrypted(plain));
+        assert_eq!(decrypt(&encrypted, "passphrase").unwrap(), plain);
+    }
+
+    #[test]
+    fn decrypt_with_wrong_passphrase() {
+        let encrypted = encrypt(br#"{"username":"user"}"#, "passphrase").unwrap();
+        let result = decrypt(&encrypted, "wrong");
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("the passphrase may be wrong"));
+    }
+}
@@ -0,0 +1,218 @@
+//! Deletion history across the runs, it is stored in the local SQLite DB given by `--db`
+//! The destroy actions skip the ids in the history of the logged-in user, and `history` shows the cumulative counts
+use anyhow::Result;
+use chrono::{SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// Destroy action recorded in the history
+/// * Delete: the tweet was deleted by [`crate::dta_app::delete_tweets()`]
+/// * Unlike: the tweet was unliked by [`crate::dta_app::unlike_likes()`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryAction {
+    Delete,
+    Unlike,
+}
+
+impl HistoryAction {
+    /// Name stored in the `action` column
+    fn as_str(&self) -> &'static str {
+        match self {
+            HistoryAction::Delete => "delete",
+            HistoryAction::Unlike => "unlike",
+        }
+    }
+}
+
+/// Cumulative counts of the history
+/// * deleted: how many tweets were deleted
+/// * unliked: how many tweets were unliked
+/// * first_at: when the first one was recorded, None if the history is empty
+/// * last_at: when the last one was recorded, None if the history is empty
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct HistoryStats {
+    pub deleted: usize,
+    pub unliked: usize,
+    pub first_at: Option<String>,
+    pub last_at: Option<String>,
+}
+
+impl std::fmt::Display for HistoryStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Deleted: {}", self.deleted)?;
+        write!(f, "Unliked: {}", self.unliked)?;
+        if let (Some(first_at), Some(last_at)) = (&self.first_at, &self.last_at) {
+            write!(f, "\nFrom {} to {}", first_at, last_at)?;
+        }
+        Ok(())
+    }
+}
+
+/// Local DB of the deletion history
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    /// Open the DB, it is created with the table if it doesn't exist
+    /// The DB without `user_id` is migrated, its records are kept with the empty `user_id`,
+    /// so they are counted by [`History::stats()`] but they are not skipped for any user
+    /// * path: path to the SQLite DB file
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = match Connection::open(path) {
+            Ok(conn) => conn,
+            Err(e) => return Err(anyhow::anyhow!("Failed to open the DB {:?}: {}", path, e)),
+        };
+        let has_user_id: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('history') WHERE name = 'user_id'",
+            [],
+            |row| row.get(0),
+        )?;
+        let has_history: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'history'",
+            [],
+            |row| row.get(0),
+        )?;
+        // 同じDBを複数のアカウントで使うため, ユーザーごとに記録する
+        if has_history && !has_user_id {
+            conn.execute_batch(
+                "BEGIN;
+                ALTER TABLE history RENAME TO history_without_user;
+                CREATE TABLE history (
+                    user_id TEXT NOT NULL,
+                    id TEXT NOT NULL,
+                    action TEXT NOT NULL,
+                    recorded_at TEXT NOT NULL,
+                    PRIMARY KEY (user_id, id, action)
+                );
+                INSERT INTO history (user_id, id, action, recorded_at)
+                    SELECT '', id, action, recorded_at FROM history_without_user;
+                DROP TABLE history_without_user;
+                COMMIT;",
+            )?;
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                user_id TEXT NOT NULL,
+                id TEXT NOT NULL,
+                action TEXT NOT NULL,
+                recorded_at TEXT NOT NULL,
+                PRIMARY KEY (user_id, id, action)
+            )",
+        )?;
+        Ok(History { conn })
+    }
+
+    /// Record the destroyed id, the first record is kept if it is recorded twice
+    /// * user_id: id of the logged-in user who destroyed it
+    /// * id: tweet id
+    /// * action: what was done for the tweet
+    pub fn record(&self, user_id: &str, id: &str, action: HistoryAction) -> Result<()> {
+        let recorded_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        self.conn.execute(
+            "INSERT OR IGNORE INTO history (user_id, id, action, recorded_at) VALUES (?1, ?2, ?3, ?4)",
+            params![user_id, id, action.as_str(), recorded_at],
+        )?;
+        Ok(())
+    }
+
+    /// Whether the id was already destroyed by the action of the user
+    /// * user_id: id of the logged-in user
+    /// * id: tweet id
+    /// * action: what was done for the tweet
+    pub fn contains(&self, user_id: &str, id: &str, action: HistoryAction) -> Result<bool> {
+        let found = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM history WHERE user_id = ?1 AND id = ?2 AND action = ?3",
+                params![user_id, id, action.as_str()],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Cumulative counts of all the runs
+    pub fn stats(&self) -> Result<HistoryStats> {
+        let count = |action: HistoryAction| -> Result<usize> {
+            let count: i64 = self.conn.query_row(
+                "SELECT COUNT(*) FROM history WHERE action = ?1",
+                params![action.as_str()],
+                |row| row.get(0),
+            )?;
+            Ok(count as usize)
+        };
+        let (first_at, last_at) = self.conn.query_row(
+            "SELECT MIN(recorded_at), MAX(recorded_at) FROM history",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(HistoryStats {
+            deleted: count(HistoryAction::Delete)?,
+            unliked: count(HistoryAction::Unlike)?,
+            first_at,
+            last_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::history::{History, HistoryAction};
+
+    #[test]
+    fn record_and_count_history() {
+        let mut db_path = std::env::temp_dir();
+        db_path.push(format!("dta4hana.test.{}.db", uuid::Uuid::new_v4()));
+        let history = History::open(&db_path).unwrap();
+        assert_eq!(history.stats().unwrap().first_at, None);
+
+        history.record("10", "1", HistoryAction::Delete).unwrap();
+        history.record("10", "1", HistoryAction::Delete).unwrap();
+        history.record("10", "2", HistoryAction::Unlike).unwrap();
+        assert!(history.contains("10", "1", HistoryAction::Delete).unwrap());
+        assert!(!history.contains("10", "1", HistoryAction::Unlike).unwrap());
+        // 同じツイートでも, 別のユーザーの履歴ではない
+        assert!(!history.contains("20", "2", HistoryAction::Unlike).unwrap());
+        drop(history);
+
+        // 別の実行でも履歴は引き継がれる
+        let history = History::open(&db_path).unwrap();
+        let stats = history.stats().unwrap();
+        assert_eq!(stats.deleted, 1);
+        assert_eq!(stats.unliked, 1);
+        assert!(stats.first_at.is_some());
+        assert!(stats
+            .to_string()
+            .starts_with("Deleted: 1\nUnliked: 1\nFrom "));
+        drop(history);
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[test]
+    fn migrate_history_without_user_id() {
+        let mut db_path = std::env::temp_dir();
+        db_path.push(format!("dta4hana.test.{}.db", uuid::Uuid::new_v4()));
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE history (
+                id TEXT NOT NULL,
+                action TEXT NOT NULL,
+                recorded_at TEXT NOT NULL,
+                PRIMARY KEY (id, action)
+            );
+            INSERT INTO history VALUES ('1', 'delete', '2022-01-01T00:00:00Z');",
+        )
+        .unwrap();
+        drop(conn);
+
+        // 誰の履歴か分からないため, 数えるだけでスキップはしない
+        let history = History::open(&db_path).unwrap();
+        assert_eq!(history.stats().unwrap().deleted, 1);
+        assert!(!history.contains("10", "1", HistoryAction::Delete).unwrap());
+        history.record("10", "1", HistoryAction::Delete).unwrap();
+        assert!(history.contains("10", "1", HistoryAction::Delete).unwrap());
+        drop(history);
+        std::fs::remove_file(&db_path).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+//! Thin HTTP layer for [`crate::twitter_client::TwitterClient`]
+//! The client builds [`HttpRequest`] and sends it through [`HttpExecutor`],
+//! so the tests can replace the network with canned responses
+use std::fmt;
+use std::io::Read;
+
+use anyhow::Result;
+use url::Url;
+
+use crate::twitter_client::AgentOptions;
+
+/// HTTP request to be sent by [`HttpExecutor`]
+/// * method: e.g. GET, POST
+/// * url: endpoint without the query
+/// * headers: request headers e.g. Authorization
+/// * query: query parameters, they are encoded by the executor
+/// * form: `application/x-www-form-urlencoded` body, no body if it is None
+#[derive(Clone, Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+    pub form: Option<Vec<(String, String)>>,
+}
+
+impl HttpRequest {
+    /// Constructs new request without headers, query and body
+    pub fn new(method: &str, url: Url) -> Self {
+        HttpRequest {
+            method: method.to_string(),
+            url,
+            headers: vec![],
+            query: vec![],
+            form: None,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn query(mut self, key: &str, value: &str) -> Self {
+        self.query.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn form(mut self, form: &[(&str, &str)]) -> Self {
+        let form = form
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        self.form.replace(form);
+        self
+    }
+}
+
+/// HTTP response returned by [`HttpExecutor`]
+/// * status: HTTP status code
+/// * headers: response headers, the names are lowercase
+/// * body: response body
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// Value of the header, the name is case-insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Error of [`HttpExecutor`], it is the same categories as `ureq::Error`
+/// * Status: the server responded with 4xx or 5xx
+/// * Transport: the request couldn't reach the server or the response couldn't be read e.g. DNS, connection reset
+#[derive(Debug)]
+pub enum HttpError {
+    Status(u16, HttpResponse),
+    Transport(String),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::Status(code, _) => write!(f, "status: {}", code),
+            HttpError::Transport(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+/// Send the HTTP request
+/// It is shared by the delete workers, so it must be `Send` + `Sync`
+pub trait HttpExecutor: Send + Sync {
+    fn call(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError>;
+
+    /// Send the request and return the body as bytes, e.g. the media files
+    /// The default one is for the text bodies e.g. the canned responses of the tests
+    fn download(&self, request: &HttpRequest) -> Result<Vec<u8>, HttpError> {
+        self.call(request)
+            .map(|response| response.body.into_bytes())
+    }
+}
+
+/// [`HttpExecutor`] with `ureq`
+pub struct UreqExecutor {
+    agent: ureq::Agent,
+}
+
+impl UreqExecutor {
+    /// Constructs new executor
+    /// * agent_options: options for the HTTP agent e.g. proxy
+    ///
+    /// It returns an error if the proxy is not a valid URL
+    pub fn new(agent_options: &AgentOptions) -> Result<Self> {
+        let mut agent_builder = ureq::AgentBuilder::new().user_agent(&agent_options.user_agent);
+        if let Some(timeout) = agent_options.timeout {
+            agent_builder = agent_builder.timeout_read(timeout).timeout_write(timeout);
+        }
+        if let Some(connect_timeout) = agent_options.connect_timeout {
+            agent_builder = agent_builder.timeout_connect(connect_timeout);
+        }
+        if let Some(proxy) = &agent_options.proxy {
+            let proxy = match ureq::Proxy::new(proxy) {
+                Ok(proxy) => proxy,
+                Err(e) => {
+                    return Err(anyhow::anyhow!(
+                        "Proxy URL is not valid: {:?}, {}",
+                        proxy,
+                        e
+                    ))
+                }
+            };
+            agent_builder = agent_builder.proxy(proxy);
+        }
+        Ok(UreqExecutor {
+            agent: agent_builder.build(),
+        })
+    }
+}
+
+impl UreqExecutor {
+    /// Send the request and return the successful response before reading the body
+    /// 4xx and 5xx are [`HttpError::Status`] with the body
+    fn send(&self, request: &HttpRequest) -> Result<ureq::Response, HttpError> {
+        let mut ureq_request = self.agent.request_url(&request.method, &request.url);
+        for (name, value) in &request.headers {
+            ureq_request = ureq_request.set(name, value);
+        }
+        for (key, value) in &request.query {
+            ureq_request = ureq_request.query(key, value);
+        }
+        let result = match &request.form {
+            Some(form) => {
+                let form: Vec<(&str, &str)> = form
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str()))
+                    .collect();
+                ureq_request.send_form(&form)
+            }
+            None => ureq_request.call(),
+        };
+        match result {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(code, response)) => {
+                Err(HttpError::Status(code, read_response(response)?))
+            }
+            Err(ureq::Error::Transport(transport)) => {
+                Err(HttpError::Transport(transport.to_string()))
+            }
+        }
+    }
+}
+
+impl HttpExecutor for UreqExecutor {
+    fn call(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        read_response(self.send(request)?)
+    }
+
+    fn download(&self, request: &HttpRequest) -> Result<Vec<u8>, HttpError> {
+        let response = self.send(request)?;
+        let mut body = Vec::new();
+        match response.into_reader().read_to_end(&mut body) {
+            Ok(_) => Ok(body),
+            Err(e) => Err(HttpError::Transport(format!(
+                "Failed to read the response: {}",
+                e
+            ))),
+        }
+    }
+}
+
+/// Read the status, headers and body of the `ureq` response
+/// Failure of reading the body is a transport error
+fn read_response(response: ureq::Response) -> Result<HttpResponse, HttpError> {
+    let status = response.status();
+    let headers = response
+        .headers_names()
+        .into_iter()
+        .filter_map(|name| {
+            let value = response.header(&name)?.to_string();
+            Some((name.to_lowercase(), value))
+        })
+        .collect();
+    match response.into_string() {
+        Ok(body) => Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        }),
+        Err(e) => Err(HttpError::Transport(format!(
+            "Failed to read the response: {}",
+            e
+        ))),
+    }
+}
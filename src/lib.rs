@@ -0,0 +1,54 @@
+//! Library of DTA4HANA, the CLI(`main.rs`) is a thin wrapper of this library
+//! Other tools can embed the deletion logic with the same Twitter Client
+//!
+//! Public API
+//! * [`twitter_client`]: Twitter API Client, use [`TwitterClientTrait`] for your own implementation or mocking
+//! * [`twitter_object`]: API response objects e.g. [`Tweet`]
+//! * [`history`]: deletion history across the runs in the local SQLite DB
+//! * [`http`]: HTTP layer of the client, implement [`http::HttpExecutor`] to replace it e.g. in your tests
+//! * [`rate_limiter`]: client-side rate limits of the endpoints shared by all the requests
+//! * [`retry`]: retry of the requests with the exponential backoff, see [`RetryPolicy`]
+//! * [`sink`]: output formats of the export, implement [`sink::TweetSink`] for your own format
+//! * [`credential_store`]: where the user credential is stored, implement [`CredentialStore`] for your own storage
+//! * [`dta_app`]: actions e.g. [`delete_tweets()`] and the persistence of the user credential
+//!
+//! Internals e.g. OAuth signature(`build_oauth_signature`) and encryption of the config file
+//! are kept private, because they can be changed without notice
+pub mod credential_store;
+pub mod dta_app;
+pub mod history;
+pub mod http;
+pub mod rate_limiter;
+pub mod retry;
+pub mod sink;
+pub mod twitter_client;
+pub mod twitter_object;
+
+mod crypto;
+
+pub use credential_store::{
+    open_store, CredentialStore, FileStore, KeyringStore, MemoryStore, StoreKind,
+};
+pub use dta_app::{
+    complete_login, confirm, count_tweets, credential_json, delete_dms, delete_scheduled,
+    delete_tweets, delete_tweets_by_ids, doctor, ensure_logged_in_user, estimate_deletion,
+    fetch_tweets, init_client, init_client_with_user_cred, init_config, load_stored_app_credential,
+    login, login_oauth2, lookup_tweets, parse_csv_ids, parse_deleted_ids, parse_max_runtime,
+    parse_tweet_ids, purge, rate_limit_status, report_tweets, request_login,
+    stored_credential_entries, unbookmark_all, unlike_likes, use_timeline_of, validate_period,
+    verify_deleted, wait_until_available, ConfigEntry, CredentialFormat, CredentialSource,
+    DeleteDmsOptions, DeleteOptions, DeleteScheduledOptions, DeletionEstimate, DeletionSummary,
+    DoctorCheck, EffectiveConfig, ExportFormat, OlderThan, PurgeSummary, RateLimitStatus,
+    TimelineCursor, TweetIdList, TweetReport, TweetStats, UnbookmarkOptions, UnknownSource,
+    UnlikeOptions, VerifyReport, DEFAULT_PROFILE,
+};
+pub use retry::RetryPolicy;
+pub use twitter_client::{
+    AgentOptions, ApiVersion, DeleteOutcome, RateLimit, TwitterAppCredential,
+    TwitterAppUserCredential, TwitterClient, TwitterClientTrait, TwitterOAuth2Credential,
+    TwitterRequestToken, UnlikeOutcome,
+};
+pub use twitter_object::{
+    Attachments, DmEvent, Includes, Media, PublicMetrics, ReferencedTweet, ScheduledPage,
+    ScheduledTweet, Tweet, User, Withheld,
+};
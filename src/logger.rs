@@ -0,0 +1,60 @@
+//! Logger setup
+//! It switches the output format between human readable text and JSON(one object per line)
+use std::io::Write;
+
+use env_logger::{Builder, Env, Target};
+use log::kv::{Error, Key, Value, VisitSource};
+use serde_json::{Map, Number};
+
+use crate::cli::LogFormat;
+
+/// Initialize the logger
+/// The logs are written to stderr, so that stdout carries only the output of the actions e.g. `--summary-json`
+/// `DTA4HANA_LOG_LEVEL` is respected in both formats, and it is prioritized over `verbose`
+/// * log_format: text is the default format of env_logger, json emits `timestamp`, `level`, `message`
+///   and key/values attached to the log record e.g. `tweet_id` and `progress`
+/// * verbose: how many `-v` flags are given, see [`default_filter()`]
+pub fn init(log_format: &LogFormat, verbose: u8) {
+    let env = Env::default().filter_or("DTA4HANA_LOG_LEVEL", default_filter(verbose));
+    let mut builder = Builder::from_env(env);
+    builder.target(Target::Stderr);
+    if let LogFormat::Json = log_format {
+        builder.format(|buf, record| {
+            let mut fields = Map::new();
+            fields.insert("timestamp".into(), buf.timestamp().to_string().into());
+            fields.insert("level".into(), record.level().as_str().into());
+            fields.insert("message".into(), record.args().to_string().into());
+            let _ = record
+                .key_values()
+                .visit(&mut JsonFieldVisitor(&mut fields));
+            writeln!(buf, "{}", serde_json::Value::Object(fields))
+        });
+    }
+    builder.init();
+}
+
+/// Log filter when `DTA4HANA_LOG_LEVEL` is not set
+/// Only the logs of this app are verbose, the dependencies keep info level
+/// * verbose: 0 for info, 1 for debug and 2 or more for trace
+fn default_filter(verbose: u8) -> &'static str {
+    match verbose {
+        0 => "info",
+        1 => "info,dta4hana=debug",
+        _ => "info,dta4hana=trace",
+    }
+}
+
+/// Collect key/values of the log record as JSON fields
+/// Numbers are kept as numbers, others are stringified
+struct JsonFieldVisitor<'a>(&'a mut Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFieldVisitor<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+        let json_value = match value.to_u64() {
+            Some(number) => serde_json::Value::Number(Number::from(number)),
+            None => serde_json::Value::String(value.to_string()),
+        };
+        self.0.insert(key.to_string(), json_value);
+        Ok(())
+    }
+}
@@ -1,64 +1,1101 @@
 //! CLI tool for deleting your twitter activities
 //! This is inspired by Delete Them All(a.k.a. 黒歴史クリーナー)
 use anyhow::{anyhow, Error};
-use env_logger::Env;
-use std::path::PathBuf;
+use chrono::{Local, Utc};
+use cli::{LogFormat, OutputFormat};
+use dta4hana::credential_store;
+use dta4hana::dta_app;
+use dta4hana::dta_app::{ConfigEntry, CredentialFormat, CredentialSource, DeletionSummary};
+use dta4hana::rate_limiter::Endpoint;
+use dta4hana::retry::RetryPolicy;
+use dta4hana::twitter_client;
+use std::ffi::OsString;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use structopt::StructOpt;
-use twitter_client::TwitterClient;
+use twitter_client::{
+    AgentOptions, ApiVersion, TwitterAppUserCredential, TwitterClient, TwitterClientTrait,
+};
 mod cli;
-mod dta_app;
-mod twitter_client;
-mod twitter_object;
+mod logger;
 
-use cli::{Action::*, CommandLineArgs};
+use cli::{Action, Action::*, CommandLineArgs};
 
 /// Entrypoint Function
 ///
-/// It will use the following environment variables (It is effective only the build time)
+/// It will use the following environment variables
 /// * `DTA4HANA_LOG_LEVEL` Log level setting e.g. `DTA4HANA_LOG_LEVEL=dta4hana=debug`
 /// * `DTA4HANA_B` Bearer Token, it will be used for retrieving the user id and login process
 /// * `DTA4HANA_CK` Consumer Key, it will be used for calling Twitter API as app
 /// * `DTA4HANA_CS` Consumer Secret, it will be used for calling Twitter API as app
-fn main() -> anyhow::Result<()> {
-    let env = Env::default().filter_or("DTA4HANA_LOG_LEVEL", "info");
-    env_logger::init_from_env(env);
-
-    // Twitter Client初期化用のKeyなど, 定義がない場合は実行時エラーにする
-    let api_key = match option_env!("DTA4HANA_B") {
-        Some(env) => env.to_string(),
-        None => return Err(anyhow::anyhow!("No value is defined in {}", "DTA4HANA_B")),
-    };
-    let consumer_key = match option_env!("DTA4HANA_CK") {
-        Some(env) => env.to_string(),
-        None => return Err(anyhow::anyhow!("No value is defined in {}", "DTA4HANA_CK")),
-    };
-    let consumer_secret = match option_env!("DTA4HANA_CS") {
-        Some(env) => env.to_string(),
-        None => return Err(anyhow::anyhow!("No value is defined in {}", "DTA4HANA_CS")),
-    };
-
+/// * `DTA4HANA_CID` OAuth 2.0 Client ID, it is optional and will be used only for `login --oauth2`
+/// * `DTA4HANA_CONFIG` Path to the config file, `--config-file` is prioritized, see [`resolve_config_file()`]
+///
+/// App credentials(`DTA4HANA_B`, `DTA4HANA_CK`, `DTA4HANA_CS` and `DTA4HANA_CID`) are resolved in this order,
+/// see [`resolve_app_credential()`]
+/// 1. CLI option e.g. `--bearer`
+/// 2. Environment variable at the runtime
+/// 3. `app` of the config file, except `DTA4HANA_CID`
+/// 4. Environment variable at the build time
+///
+/// Output streams
+/// * stdout: only the results of the actions e.g. `count`, `doctor`, `--summary-json` and `login --print-credential json`
+/// * stderr: the logs, the prompts and the progress bar, see [`logger::init()`]
+///
+/// Exit status, see [`exit_code()`]
+/// * 0: the action was done, e.g. some tweets were deleted
+/// * 1: the action was failed, or `verify` found the remaining tweets
+/// * 2: nothing to delete for the destroy actions e.g. the timeline is empty
+fn main() -> anyhow::Result<ExitCode> {
     let CommandLineArgs {
         action,
         config_file,
+        work_dir,
+        db,
+        profile,
+        verbose,
+        quiet,
+        log_format,
+        bearer,
+        consumer_key,
+        consumer_secret,
+        oauth_token,
+        oauth_token_secret,
+        passphrase,
+        store: store_kind,
+        proxy,
+        timeout_secs,
+        connect_timeout_secs,
+        max_retries,
+        retry_base_delay_ms,
+        retry_max_delay_ms,
+        retry_jitter_ms,
+        rate_limits,
+        api_version,
+        user_agent,
+        jitter_ms,
+        page_interval_ms,
+        max_runtime,
+        wait_until_available,
+        wait_timeout,
+        page_size,
+        fields,
+        expansions,
+        user,
+        yes,
+        no_progress,
+        summary_json,
     } = CommandLineArgs::from_args();
+    // ログインや取得の時間も含めて, 実行全体の時間を制限する
+    let deadline = max_runtime.map(|max_runtime| Instant::now() + max_runtime);
 
-    let config_file = match config_file {
-        Some(config_file) => config_file,
-        None => find_default_config_file()?,
+    logger::init(&log_format, verbose);
+    let progress = !no_progress && is_progress_available(&log_format);
+
+    let config_file = resolve_config_file(config_file, std::env::var_os("DTA4HANA_CONFIG"))?;
+    let work_dir = match work_dir {
+        Some(work_dir) => work_dir,
+        None => default_work_dir(&config_file),
     };
+    let passphrase = passphrase.as_deref();
 
-    let tw_client: TwitterClient =
-        dta_app::init_client(api_key, consumer_key, consumer_secret, &config_file)?;
+    // CLIでユーザーの認証情報が与えられた場合は, 設定ファイルを読み書きしない
+    let given_user_cred = match (oauth_token, oauth_token_secret) {
+        (Some(oauth_token), Some(oauth_token_secret)) => Some((oauth_token, oauth_token_secret)),
+        _ => None,
+    };
+    if given_user_cred.is_some() && matches!(action, Login { .. } | ConfigInit { .. }) {
+        return Err(anyhow!(
+            "--oauth-token can't be used with login and config-init, the given credential is never stored"
+        ));
+    }
+
+    // 設定ファイルのApp認証情報は, ビルド時の値より優先する
+    let stored_app_cred = match given_user_cred {
+        Some(_) => None,
+        None => dta_app::load_stored_app_credential(&config_file, passphrase),
+    };
+    let stored_api_key = stored_app_cred.as_ref().map(|cred| cred.api_key.as_str());
+    let stored_consumer_key = stored_app_cred
+        .as_ref()
+        .map(|cred| cred.consumer_key.as_str());
+    let stored_consumer_secret = stored_app_cred
+        .as_ref()
+        .map(|cred| cred.consumer_secret.as_str());
+    // config-showが解決元を表示するため, CLIの値を残しておく
+    let app_credential_args = [
+        (
+            "bearer",
+            bearer.clone(),
+            "DTA4HANA_B",
+            stored_api_key,
+            option_env!("DTA4HANA_B"),
+        ),
+        (
+            "consumer_key",
+            consumer_key.clone(),
+            "DTA4HANA_CK",
+            stored_consumer_key,
+            option_env!("DTA4HANA_CK"),
+        ),
+        (
+            "consumer_secret",
+            consumer_secret.clone(),
+            "DTA4HANA_CS",
+            stored_consumer_secret,
+            option_env!("DTA4HANA_CS"),
+        ),
+        (
+            "client_id",
+            None,
+            "DTA4HANA_CID",
+            None,
+            option_env!("DTA4HANA_CID"),
+        ),
+    ];
+    let api_key = resolve_app_credential(
+        bearer,
+        "DTA4HANA_B",
+        stored_api_key,
+        option_env!("DTA4HANA_B"),
+    );
+    let consumer_key = resolve_app_credential(
+        consumer_key,
+        "DTA4HANA_CK",
+        stored_consumer_key,
+        option_env!("DTA4HANA_CK"),
+    );
+    let consumer_secret = resolve_app_credential(
+        consumer_secret,
+        "DTA4HANA_CS",
+        stored_consumer_secret,
+        option_env!("DTA4HANA_CS"),
+    );
+
+    let agent_options = AgentOptions {
+        proxy,
+        timeout: timeout_from_secs(timeout_secs),
+        connect_timeout: timeout_from_secs(connect_timeout_secs),
+        retry_policy: RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(retry_base_delay_ms),
+            max_delay: Duration::from_millis(retry_max_delay_ms),
+            jitter: Duration::from_millis(retry_jitter_ms),
+        },
+        rate_limits,
+        user_agent: user_agent.unwrap_or_else(|| twitter_client::DEFAULT_USER_AGENT.to_string()),
+        wait_until_available: wait_until_available
+            .then(|| wait_timeout.unwrap_or(dta_app::DEFAULT_AVAILABILITY_TIMEOUT)),
+    };
+
+    // 設定の表示は診断用のため, 認証情報がなくても Twitter Clientの初期化前に実行する
+    if let ConfigShow { format } = &action {
+        let mut entries = vec![
+            ConfigEntry::new("config_file", Some(config_file.display())),
+            ConfigEntry::new("profile", Some(&profile)),
+            ConfigEntry::new("store", Some(format!("{:?}", store_kind))),
+            ConfigEntry::new("work_dir", Some(work_dir.display())),
+            ConfigEntry::new("db", db.as_ref().map(|db| db.display())),
+            ConfigEntry::new("server", Some(twitter_client::API_URL)),
+            ConfigEntry::new(
+                "proxy",
+                agent_options
+                    .proxy
+                    .as_deref()
+                    .map(dta_app::mask_url_password),
+            ),
+            ConfigEntry::new("timeout_secs", Some(timeout_secs)),
+            ConfigEntry::new("connect_timeout_secs", Some(connect_timeout_secs)),
+            ConfigEntry::new("max_retries", Some(max_retries)),
+            ConfigEntry::new("retry_base_delay_ms", Some(retry_base_delay_ms)),
+            ConfigEntry::new("retry_max_delay_ms", Some(retry_max_delay_ms)),
+            ConfigEntry::new("retry_jitter_ms", Some(retry_jitter_ms)),
+            ConfigEntry::new(
+                "rate_limits",
+                Some(describe_rate_limits(&agent_options.rate_limits)),
+            ),
+            ConfigEntry::new(
+                "api_version",
+                api_version.map(|api_version| format!("{:?}", api_version)),
+            ),
+            ConfigEntry::new("user_agent", Some(&agent_options.user_agent)),
+            ConfigEntry::new("interval_ms", Some(dta_app::REQUEST_INTERVAL_MILLIS)),
+            ConfigEntry::new("jitter_ms", Some(jitter_ms)),
+            ConfigEntry::new("page_size", Some(page_size)),
+            ConfigEntry::new("page_interval_ms", Some(page_interval_ms)),
+            ConfigEntry::new(
+                "max_runtime_secs",
+                max_runtime.map(|max_runtime| max_runtime.as_secs()),
+            ),
+            ConfigEntry::new(
+                "wait_until_available_secs",
+                agent_options
+                    .wait_until_available
+                    .map(|timeout| timeout.as_secs()),
+            ),
+            ConfigEntry::new("fields", fields.as_ref()),
+            ConfigEntry::new("expansions", expansions.as_ref()),
+            ConfigEntry::new("user", user.as_ref()),
+        ];
+        for (name, cli_value, env_name, stored_value, build_time_value) in app_credential_args {
+            let resolved = resolve_app_credential_with_source(
+                cli_value,
+                env_name,
+                stored_value,
+                build_time_value,
+            );
+            entries.push(match &resolved {
+                Some((value, source)) => ConfigEntry::secret(name, Some(value), Some(*source)),
+                None => ConfigEntry::secret(name, None, None),
+            });
+        }
+        match &given_user_cred {
+            Some((oauth_token, oauth_token_secret)) => {
+                let from_cli = Some(CredentialSource::Cli);
+                entries.push(ConfigEntry::secret(
+                    "oauth_token",
+                    Some(oauth_token),
+                    from_cli,
+                ));
+                entries.push(ConfigEntry::secret(
+                    "oauth_token_secret",
+                    Some(oauth_token_secret),
+                    from_cli,
+                ));
+            }
+            None => entries.append(&mut dta_app::stored_credential_entries(
+                &config_file,
+                &profile,
+                passphrase,
+            )),
+        }
+        let config = dta_app::EffectiveConfig { entries };
+        match format {
+            OutputFormat::Text => println!("{}", config),
+            OutputFormat::Json => println!("{}", serde_json::to_string(&config.entries)?),
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // テンプレートの作成には認証情報が不要なため, Twitter Clientの初期化前に実行する
+    if let ConfigInit { force } = action {
+        dta_app::init_config(&config_file, &profile, force)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // 履歴はローカルのDBだけで集計できるため, Twitter Clientの初期化前に実行する
+    if let History = action {
+        let db = match &db {
+            Some(db) => db,
+            None => return Err(anyhow!("History requires --db <path>")),
+        };
+        let stats = dta4hana::history::History::open(db)?.stats()?;
+        println!("{}", stats);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // 不正な日付でAPIを呼ばないよう, Twitter Clientの初期化前に期間を確認する
+    let (since, until) = period_of(&action)?;
+    dta_app::validate_period(&since, &until)?;
+
+    // Doctorは認証情報の不足も報告するため, Twitter Clientの初期化前に実行する
+    if let Doctor = action {
+        let checks = dta_app::doctor(
+            api_key,
+            consumer_key,
+            consumer_secret,
+            &config_file,
+            &profile,
+            passphrase,
+            &agent_options,
+        );
+        for check in &checks {
+            println!("{}", check);
+        }
+        let failed = checks.iter().filter(|check| !check.is_ok()).count();
+        return match failed {
+            0 => Ok(ExitCode::SUCCESS),
+            failed => Err(anyhow!("{} of {} checks failed", failed, checks.len())),
+        };
+    }
+
+    // プロンプトを使わないログインは, 保存済みの認証情報を読み込まずに実行する
+    if let Login {
+        username: Some(username),
+        ..
+    } = &action
+    {
+        let tw_client = TwitterClient::new(
+            api_key?,
+            consumer_key?,
+            consumer_secret?,
+            None,
+            &agent_options,
+        )?;
+        let authorize_url = dta_app::request_login(&tw_client, username, &profile, &work_dir)?;
+        println!("{}", authorize_url);
+        return Ok(ExitCode::SUCCESS);
+    }
+    // キーチェーンが使えない場合は, 警告してファイルに保存する
+    let store = credential_store::open_store(store_kind, &config_file, &profile, passphrase);
+    if let Login {
+        pin: Some(pin),
+        print_credential,
+        ..
+    } = &action
+    {
+        let tw_client = TwitterClient::new(
+            api_key?,
+            consumer_key?,
+            consumer_secret?,
+            None,
+            &agent_options,
+        )?;
+        let user_cred =
+            dta_app::complete_login(&tw_client, pin, &work_dir, &profile, store.as_ref())?;
+        print_login_credential(&user_cred, print_credential)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Twitter Client初期化用のKeyなど, 定義がない場合は実行時エラーにする
+    let tw_client: TwitterClient = match given_user_cred {
+        Some((oauth_token, oauth_token_secret)) => dta_app::init_client_with_user_cred(
+            api_key?,
+            consumer_key?,
+            consumer_secret?,
+            oauth_token,
+            oauth_token_secret,
+            &agent_options,
+        )?,
+        None => dta_app::init_client(
+            api_key?,
+            consumer_key?,
+            consumer_secret?,
+            store.as_ref(),
+            &agent_options,
+            !matches!(action, Login { .. }),
+        )?,
+    };
+    let tw_client = match &fields {
+        Some(fields) => tw_client.with_tweet_fields(fields),
+        None => tw_client,
+    };
+    let tw_client = match &expansions {
+        Some(expansions) => tw_client.with_expansions(expansions),
+        None => tw_client,
+    };
+    let tw_client = match api_version {
+        Some(api_version) => tw_client.with_api_version(api_version),
+        None => tw_client,
+    };
+    let tw_client = tw_client
+        .with_page_size(page_size)?
+        .with_page_interval(Duration::from_millis(page_interval_ms));
+    let tw_client = match &user {
+        Some(user) if matches!(action, Count { .. } | Report { .. } | Fetch { .. }) => {
+            dta_app::use_timeline_of(tw_client, user)?
+        }
+        Some(user) => {
+            dta_app::ensure_logged_in_user(&tw_client, user)?;
+            tw_client
+        }
+        None => tw_client,
+    };
+    // 途中で403にならないよう, OAuth 2.0のスコープを事前に確認する
+    match &action {
+        Delete { dry_run: false, .. } | Purge { .. }
+            if tw_client.api_version() == ApiVersion::V2 =>
+        {
+            tw_client.require_oauth2_scopes(twitter_client::DELETE_TWEET_SCOPES)?
+        }
+        Unbookmark => tw_client.require_oauth2_scopes(twitter_client::UNBOOKMARK_SCOPES)?,
+        DeleteDms { .. } => tw_client.require_oauth2_scopes(twitter_client::DELETE_DMS_SCOPES)?,
+        _ => {}
+    }
 
     match action {
-        Delete { since, until } => dta_app::delete_tweets(&tw_client, since, until),
-        Fetch { since, until } => dta_app::fetch_tweets(&tw_client, since, until),
-        Login => dta_app::login(&tw_client, &config_file),
-        Unlike => dta_app::unlike_likes(&tw_client),
-    }?;
+        Delete {
+            since,
+            until,
+            older_than,
+            since_id,
+            until_id,
+            concurrency,
+            exclude_media,
+            media_only,
+            exclude_replies,
+            replies_only,
+            retweets_only,
+            retweets_of,
+            include_pinned,
+            max,
+            keep_likes,
+            keep_retweets,
+            protect_engaged,
+            smart_purge,
+            keep_recent,
+            shuffle,
+            seed,
+            from_source,
+            not_from_source,
+            unknown_source,
+            lang,
+            not_lang,
+            delete_und,
+            only_sensitive,
+            exclude_withheld,
+            contains,
+            matches,
+            backup,
+            no_backup,
+            output_dir,
+            download_media,
+            dry_run,
+            fail_fast,
+            max_failures,
+            from_stdin,
+            from_csv,
+        } => {
+            // dry runは何も削除しないため, 自動では保存しない
+            let backup = if dry_run {
+                backup
+            } else {
+                backup_path(backup, no_backup, output_dir, &work_dir, &tw_client)?
+            };
+            let until = match older_than {
+                Some(older_than) => Some(older_than.until(Utc::now().date_naive())?),
+                None => until,
+            };
+            let tw_client = tw_client.with_id_range(since_id.as_deref(), until_id.as_deref())?;
+            // smart purgeは, 指定がなければ既定のしきい値で人気のツイートを残す
+            let (keep_likes, keep_retweets) = if smart_purge {
+                (
+                    keep_likes.or(Some(dta_app::SMART_PURGE_KEEP_LIKES)),
+                    keep_retweets.or(Some(dta_app::SMART_PURGE_KEEP_RETWEETS)),
+                )
+            } else {
+                (keep_likes, keep_retweets)
+            };
+            let tw_client = match download_media {
+                Some(_) => tw_client.with_media_expansion(),
+                None => tw_client,
+            };
+            // リツイート元の作者で絞り込むため, ユーザーIDを解決して, リツイート元のツイートを展開する
+            let (tw_client, retweets_of) = match retweets_of {
+                Some(username) => {
+                    let user = tw_client.lookup_user(username.trim_start_matches('@'))?;
+                    (tw_client.with_referenced_authors(), Some(user))
+                }
+                None => (tw_client, None),
+            };
+            let options = dta_app::DeleteOptions {
+                since,
+                until,
+                since_id,
+                until_id,
+                // 既定値は--from-stdinと衝突しないよう, ここで補う
+                concurrency: concurrency.unwrap_or(1),
+                exclude_media,
+                media_only,
+                exclude_replies,
+                replies_only,
+                retweets_only,
+                retweets_of,
+                max,
+                keep_likes,
+                keep_retweets,
+                protect_engaged,
+                smart_purge,
+                keep_recent,
+                shuffle,
+                seed,
+                from_source,
+                not_from_source,
+                unknown_source,
+                lang,
+                not_lang,
+                delete_und,
+                only_sensitive,
+                exclude_withheld,
+                contains,
+                matches,
+                backup,
+                db,
+                user_id: tw_client.user_id().map(str::to_string),
+                download_media,
+                dry_run,
+                fail_fast,
+                // 既定値はfail-fastと衝突しないよう, ここで補う
+                max_failures: Some(max_failures.unwrap_or(dta_app::DEFAULT_MAX_FAILURES)),
+                pinned_tweet_id: if include_pinned {
+                    None
+                } else {
+                    tw_client.pinned_tweet_id().map(str::to_string)
+                },
+                jitter: Duration::from_millis(jitter_ms),
+                progress,
+                quiet,
+                deadline,
+                ..Default::default()
+            };
+            // --fieldsで絞っても, フィルタが読む項目は取得する
+            let tw_client = tw_client.with_required_tweet_fields(&options.required_tweet_fields());
+            let given = if from_stdin {
+                let mut input = String::new();
+                std::io::stdin().read_to_string(&mut input)?;
+                Some((dta_app::parse_tweet_ids(&input), String::from("stdin")))
+            } else if let Some(from_csv) = &from_csv {
+                let input = match std::fs::read_to_string(from_csv) {
+                    Ok(input) => input,
+                    Err(e) => return Err(anyhow!("Failed to read {:?}: {}", from_csv, e)),
+                };
+                let list = dta_app::parse_csv_ids(&input)?;
+                Some((list, format!("{:?}", from_csv)))
+            } else {
+                None
+            };
+            if let Some((list, given_by)) = given {
+                // stdinはIDの入力に使われるため, 確認には--yesが必要
+                if !dry_run {
+                    dta_app::confirm(
+                        &format!("Delete {} tweets given by {}", list.ids.len(), given_by),
+                        yes,
+                    )?;
+                }
+                set_interrupt_handler(options.interrupted.clone())?;
+                let result = dta_app::delete_tweets_by_ids(&tw_client, &list, &options);
+                let attempted = result.as_ref().ok().map(|summary| summary.attempted);
+                print_backup_path(options.backup.as_deref(), attempted);
+                return result.and_then(|summary| finish(&summary, summary_json));
+            }
+            // dry runは何も削除しないため, 確認しない
+            if !dry_run {
+                dta_app::estimate_deletion(&tw_client, &options)?;
+                dta_app::confirm(&options.describe_scope(), yes)?;
+            }
+            set_interrupt_handler(options.interrupted.clone())?;
+            let result = dta_app::delete_tweets(&tw_client, &options);
+            let attempted = result.as_ref().ok().map(|summary| summary.attempted);
+            print_backup_path(options.backup.as_deref(), attempted);
+            result.and_then(|summary| finish(&summary, summary_json))
+        }
+        DeleteDms { since, until } => {
+            let options = dta_app::DeleteDmsOptions {
+                since,
+                until,
+                quiet,
+                ..Default::default()
+            };
+            dta_app::confirm(&options.describe_scope(), yes)?;
+            set_interrupt_handler(options.interrupted.clone())?;
+            dta_app::delete_dms(&tw_client, &options)
+                .and_then(|summary| finish(&summary, summary_json))
+        }
+        DeleteScheduled {
+            account_id,
+            dry_run,
+        } => {
+            let options = dta_app::DeleteScheduledOptions {
+                account_id,
+                dry_run,
+                quiet,
+                ..Default::default()
+            };
+            // dry runは何も削除しないため, 確認しない
+            if !dry_run {
+                dta_app::confirm(&options.describe_scope(), yes)?;
+            }
+            set_interrupt_handler(options.interrupted.clone())?;
+            dta_app::delete_scheduled(&tw_client, &options)
+                .and_then(|summary| finish(&summary, summary_json))
+        }
+        ConfigInit { .. } | ConfigShow { .. } | Doctor | History => {
+            unreachable!("{:?} is handled before initializing the client", action)
+        }
+        Count {
+            since,
+            until,
+            since_id,
+            until_id,
+            start_token,
+        } => {
+            let tw_client = tw_client.with_id_range(since_id.as_deref(), until_id.as_deref())?;
+            let cursor = dta_app::TimelineCursor {
+                start_token,
+                ..Default::default()
+            };
+            set_interrupt_handler(cursor.interrupted.clone())?;
+            let (stats, next_token) = dta_app::count_tweets(&tw_client, since, until, &cursor)?;
+            println!("{}", stats);
+            print_next_token(next_token);
+            Ok(ExitCode::SUCCESS)
+        }
+        Report {
+            since,
+            until,
+            since_id,
+            until_id,
+            start_token,
+            format,
+        } => {
+            let tw_client = tw_client.with_id_range(since_id.as_deref(), until_id.as_deref())?;
+            let cursor = dta_app::TimelineCursor {
+                start_token,
+                ..Default::default()
+            };
+            set_interrupt_handler(cursor.interrupted.clone())?;
+            let (report, next_token) = dta_app::report_tweets(&tw_client, since, until, &cursor)?;
+            match format {
+                OutputFormat::Text => println!("{}", report),
+                OutputFormat::Json => println!("{}", serde_json::to_string(&report)?),
+            }
+            print_next_token(next_token);
+            Ok(ExitCode::SUCCESS)
+        }
+        RateLimit { format } => {
+            let statuses = dta_app::rate_limit_status(&tw_client);
+            match format {
+                OutputFormat::Text => {
+                    for status in &statuses {
+                        println!("{}", status);
+                    }
+                }
+                OutputFormat::Json => println!("{}", serde_json::to_string(&statuses)?),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Fetch {
+            since,
+            until,
+            since_id,
+            until_id,
+            output,
+            output_template,
+            format,
+            start_token,
+        } => {
+            let output = match output_template {
+                Some(template) => {
+                    let username = match (&user, tw_client.username()) {
+                        (Some(user), _) => user.trim_start_matches('@'),
+                        (None, Some(username)) => username,
+                        (None, None) => "",
+                    };
+                    let values = dta_app::OutputTemplateValues {
+                        username,
+                        date: &Utc::now().date_naive().to_string(),
+                        action: "fetch",
+                    };
+                    let file_name = dta_app::render_output_template(&template, &values)?;
+                    Some(dta_app::work_file_path(&work_dir, &file_name)?)
+                }
+                None => output,
+            };
+            let tw_client = tw_client.with_id_range(since_id.as_deref(), until_id.as_deref())?;
+            let cursor = dta_app::TimelineCursor {
+                start_token,
+                ..Default::default()
+            };
+            set_interrupt_handler(cursor.interrupted.clone())?;
+            let (_, next_token) = dta_app::fetch_tweets(
+                &tw_client,
+                since,
+                until,
+                &work_dir,
+                output.as_deref(),
+                &format,
+                &cursor,
+            )?;
+            // 出力と混ざらないように, 標準出力へのエクスポートではstderrに出す
+            match next_token {
+                Some(next_token)
+                    if output.as_deref() == Some(Path::new(dta_app::STDOUT_OUTPUT)) =>
+                {
+                    eprintln!("Next token: {}", next_token)
+                }
+                next_token => print_next_token(next_token),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Login {
+            oauth2,
+            print_credential,
+            open,
+            scopes,
+            ..
+        } => {
+            let tw_client = tw_client.with_open_browser(open);
+            let tw_client = match &scopes {
+                Some(scopes) => tw_client.with_oauth2_scopes(scopes),
+                None => tw_client,
+            };
+            let user_cred = if oauth2 {
+                let client_id = resolve_app_credential(
+                    None,
+                    "DTA4HANA_CID",
+                    None,
+                    option_env!("DTA4HANA_CID"),
+                )?;
+                dta_app::login_oauth2(&tw_client, store.as_ref(), &client_id)?
+            } else {
+                dta_app::login(&tw_client, store.as_ref())?
+            };
+            print_login_credential(&user_cred, &print_credential)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Unbookmark => {
+            let options = dta_app::UnbookmarkOptions {
+                jitter: Duration::from_millis(jitter_ms),
+                progress,
+                quiet,
+                deadline,
+                ..Default::default()
+            };
+            dta_app::confirm("Unbookmark all your bookmarked tweets", yes)?;
+            set_interrupt_handler(options.interrupted.clone())?;
+            dta_app::unbookmark_all(&tw_client, &options)
+                .and_then(|summary| finish(&summary, summary_json))
+        }
+        Unlike {
+            max,
+            older_than,
+            until,
+        } => {
+            let until = match older_than {
+                Some(older_than) => Some(older_than.until(Utc::now().date_naive())?),
+                None => until,
+            };
+            let options = dta_app::UnlikeOptions {
+                max,
+                until,
+                jitter: Duration::from_millis(jitter_ms),
+                db,
+                user_id: tw_client.user_id().map(str::to_string),
+                progress,
+                quiet,
+                deadline,
+                ..Default::default()
+            };
+            dta_app::confirm(&options.describe_scope(), yes)?;
+            set_interrupt_handler(options.interrupted.clone())?;
+            dta_app::unlike_likes(&tw_client, &options)
+                .and_then(|summary| finish(&summary, summary_json))
+        }
+        Purge {
+            since,
+            older_than,
+            until,
+            no_backup,
+            output_dir,
+        } => {
+            let until = match older_than {
+                Some(older_than) => Some(older_than.until(Utc::now().date_naive())?),
+                None => until,
+            };
+            let delete_options = dta_app::DeleteOptions {
+                since,
+                until: until.clone(),
+                backup: backup_path(None, no_backup, output_dir, &work_dir, &tw_client)?,
+                db: db.clone(),
+                user_id: tw_client.user_id().map(str::to_string),
+                pinned_tweet_id: tw_client.pinned_tweet_id().map(str::to_string),
+                jitter: Duration::from_millis(jitter_ms),
+                progress,
+                quiet,
+                deadline,
+                ..Default::default()
+            };
+            // Ctrl-Cで両方を止めるため, 中断のフラグを共有する
+            let unlike_options = dta_app::UnlikeOptions {
+                until,
+                jitter: Duration::from_millis(jitter_ms),
+                db,
+                user_id: tw_client.user_id().map(str::to_string),
+                progress,
+                quiet,
+                interrupted: delete_options.interrupted.clone(),
+                deadline,
+                ..Default::default()
+            };
+            dta_app::estimate_deletion(&tw_client, &delete_options)?;
+            dta_app::confirm(
+                &format!(
+                    "{}. {}",
+                    delete_options.describe_scope(),
+                    unlike_options.describe_scope()
+                ),
+                yes,
+            )?;
+            set_interrupt_handler(delete_options.interrupted.clone())?;
+            let result = dta_app::purge(&tw_client, &delete_options, &unlike_options);
+            let attempted = result.as_ref().ok().map(|summary| summary.tweets.attempted);
+            print_backup_path(delete_options.backup.as_deref(), attempted);
+            let summary = result?;
+            if summary_json {
+                println!("{}", serde_json::to_string(&summary)?);
+            }
+            Ok(exit_code(&summary.total()))
+        }
+        Verify { file } => {
+            let input = match std::fs::read_to_string(&file) {
+                Ok(input) => input,
+                Err(e) => return Err(anyhow!("Failed to read {:?}: {}", file, e)),
+            };
+            let list = dta_app::parse_deleted_ids(&input)?;
+            let report = dta_app::verify_deleted(&tw_client, &list.ids)?;
+            // 残っているIDは `delete --from-stdin` にそのまま渡せる
+            for id in &report.remaining {
+                println!("{}", id);
+            }
+            if report.remaining.is_empty() {
+                Ok(ExitCode::SUCCESS)
+            } else {
+                Ok(ExitCode::FAILURE)
+            }
+        }
+    }
+}
+
+/// Print `next_token` to resume the read-only actions with `--start-token` in the next run
+/// Nothing is printed if all the pages were walked through
+fn print_next_token(next_token: Option<String>) {
+    if let Some(next_token) = next_token {
+        println!("Next token: {}", next_token);
+    }
+}
+
+/// Print the non-secret parts of the credential after login, see [`dta_app::credential_json()`]
+/// * user_cred: logged in user credential
+/// * format: how to print it
+fn print_login_credential(
+    user_cred: &TwitterAppUserCredential,
+    format: &CredentialFormat,
+) -> Result<(), Error> {
+    if let CredentialFormat::Json = format {
+        println!("{}", dta_app::credential_json(user_cred)?);
+    }
     Ok(())
 }
 
+/// Exit status when there was nothing to delete, scripts e.g. cron jobs can branch on it
+const EXIT_NOTHING_TO_DELETE: u8 = 2;
+
+/// Exit status of the destroy actions, the errors are exit status 1 by returning them from [`main()`]
+/// * summary: result of the destroy action
+fn exit_code(summary: &DeletionSummary) -> ExitCode {
+    if summary.is_nothing_to_delete() {
+        ExitCode::from(EXIT_NOTHING_TO_DELETE)
+    } else if summary.deleted == 0 && summary.skipped > 0 {
+        // 対象はあったが1件も削除できなかった場合は, 失敗として扱う
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Print the summary if it is requested, and decide the exit status by [`exit_code()`]
+/// * summary: result of the destroy action
+/// * summary_json: whether to print the summary as a JSON object to stdout
+fn finish(summary: &DeletionSummary, summary_json: bool) -> Result<ExitCode, Error> {
+    if summary_json {
+        println!("{}", serde_json::to_string(summary)?);
+    }
+    Ok(exit_code(summary))
+}
+
+/// Backup file of the deleted tweets, they are backed up automatically unless `--no-backup` is given
+/// * backup: file given by `--backup`, it is used instead of the automatic backup
+/// * no_backup: whether the automatic backup is disabled
+/// * output_dir: directory of the automatic backup, [`dta_app::BACKUP_DIR_NAME`] in the work directory by default
+/// * work_dir: work directory of the run
+/// * tw_client: logged-in client, its username names the backup file
+fn backup_path(
+    backup: Option<PathBuf>,
+    no_backup: bool,
+    output_dir: Option<PathBuf>,
+    work_dir: &Path,
+    tw_client: &TwitterClient,
+) -> Result<Option<PathBuf>, Error> {
+    if backup.is_some() || no_backup {
+        return Ok(backup);
+    }
+    let output_dir = match output_dir {
+        Some(output_dir) => output_dir,
+        None => work_dir.join(dta_app::BACKUP_DIR_NAME),
+    };
+    let username = tw_client.username().unwrap_or("unknown");
+    let backup = dta_app::auto_backup_path(&output_dir, username, &Local::now())?;
+    Ok(Some(backup))
+}
+
+/// Show where the deleted tweets were backed up, only if any deletion was attempted
+/// If the run failed on the way, it is shown if the backup file has any tweet
+/// * backup: backup file of the run, nothing is shown if it is None
+/// * attempted: how many deletions were attempted, None if the run failed on the way
+fn print_backup_path(backup: Option<&Path>, attempted: Option<usize>) {
+    let backup = match backup {
+        Some(backup) => backup,
+        None => return,
+    };
+    let written = match attempted {
+        Some(attempted) => attempted > 0,
+        None => std::fs::metadata(backup).is_ok_and(|metadata| metadata.len() > 0),
+    };
+    if written {
+        eprintln!("The deleted tweets are backed up to {}", backup.display());
+    }
+}
+
+/// Target period of the action for [`dta_app::validate_period()`]
+/// `--older-than` is resolved with today in UTC as the action does
+/// * action: the action to run
+fn period_of(action: &Action) -> Result<(Option<String>, Option<String>), Error> {
+    let (since, until, older_than) = match action {
+        Delete {
+            since,
+            until,
+            older_than,
+            ..
+        }
+        | Purge {
+            since,
+            until,
+            older_than,
+            ..
+        } => (since.clone(), until.clone(), *older_than),
+        Unlike {
+            until, older_than, ..
+        } => (None, until.clone(), *older_than),
+        DeleteDms { since, until }
+        | Count { since, until, .. }
+        | Report { since, until, .. }
+        | Fetch { since, until, .. } => (since.clone(), until.clone(), None),
+        _ => (None, None, None),
+    };
+    let until = match older_than {
+        Some(older_than) => Some(older_than.until(Utc::now().date_naive())?),
+        None => until,
+    };
+    Ok((since, until))
+}
+
+/// Handle Ctrl-C to stop the destroy actions gracefully
+/// The first Ctrl-C sets `interrupted` and the action stops after the in-flight requests, the second one exits immediately
+/// It should be set after the user inputs e.g. login and confirmation, so that Ctrl-C can abort them as usual
+/// * interrupted: the flag checked by the action
+fn set_interrupt_handler(interrupted: Arc<AtomicBool>) -> Result<(), Error> {
+    let result = ctrlc::set_handler(move || {
+        if interrupted.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        eprintln!("Interrupted, finishing the in-flight requests... (press Ctrl-C again to exit immediately)");
+    });
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("Failed to set Ctrl-C handler: {}", e)),
+    }
+}
+
+/// Whether the progress bar can be drawn instead of the log per tweet
+/// It requires the terminal and human readable logs, and debug logs are not hidden by the bar
+/// The bar is drawn to stderr as the logs, so stdout can be piped e.g. `--summary-json`
+fn is_progress_available(log_format: &LogFormat) -> bool {
+    matches!(log_format, LogFormat::Text)
+        && log::max_level() < log::LevelFilter::Debug
+        && std::io::stderr().is_terminal()
+}
+
+/// Resolve the app credential from CLI option, runtime env, config file and build time env
+/// The former one is prioritized, and it returns an error if none of them is defined
+/// * cli_value: value given by CLI option
+/// * env_name: name of the environment variable
+/// * stored_value: value stored in the config file, see [`dta_app::load_stored_app_credential()`]
+/// * build_time_value: value of the environment variable at the build time i.e. `option_env!(env_name)`
+fn resolve_app_credential(
+    cli_value: Option<String>,
+    env_name: &str,
+    stored_value: Option<&str>,
+    build_time_value: Option<&str>,
+) -> Result<String, Error> {
+    match resolve_app_credential_with_source(cli_value, env_name, stored_value, build_time_value) {
+        Some((value, _)) => Ok(value),
+        None => Err(anyhow!("No value is defined in {}", env_name)),
+    }
+}
+
+/// [`resolve_app_credential()`] with where it was resolved from, None if it is not defined anywhere
+fn resolve_app_credential_with_source(
+    cli_value: Option<String>,
+    env_name: &str,
+    stored_value: Option<&str>,
+    build_time_value: Option<&str>,
+) -> Option<(String, CredentialSource)> {
+    if let Some(value) = cli_value {
+        return Some((value, CredentialSource::Cli));
+    }
+    if let Ok(value) = std::env::var(env_name) {
+        return Some((value, CredentialSource::RuntimeEnv));
+    }
+    if let Some(value) = stored_value {
+        return Some((value.to_string(), CredentialSource::ConfigFile));
+    }
+    build_time_value.map(|value| (value.to_string(), CredentialSource::BuildEnv))
+}
+
+/// Describe the overrides of `--rate-limit` for `config-show` e.g. `delete_tweet=30`
+/// The endpoints which are not overridden use [`Endpoint::default_quota()`]
+fn describe_rate_limits(rate_limits: &[(Endpoint, u32)]) -> String {
+    if rate_limits.is_empty() {
+        return String::from("(default)");
+    }
+    let overrides: Vec<String> = rate_limits
+        .iter()
+        .map(|(endpoint, quota)| format!("{}={}", endpoint.name(), quota))
+        .collect();
+    overrides.join(",")
+}
+
+/// Convert the timeout option, 0 means no timeout
+fn timeout_from_secs(secs: u64) -> Option<Duration> {
+    match secs {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// Resolve the path of the config file from CLI option, env and the default path in the home dir
+/// The former one is prioritized, and the empty env is regarded as undefined
+/// * cli_value: value given by `--config-file`
+/// * env_value: value of `DTA4HANA_CONFIG`
+///
+/// It returns an error if the directory of the path given by the env doesn't exist
+fn resolve_config_file(
+    cli_value: Option<PathBuf>,
+    env_value: Option<OsString>,
+) -> Result<PathBuf, Error> {
+    if let Some(config_file) = cli_value {
+        return Ok(config_file);
+    }
+    match env_value {
+        Some(env_value) if !env_value.is_empty() => {
+            let config_file = PathBuf::from(env_value);
+            let parent = default_work_dir(&config_file);
+            if !parent.is_dir() {
+                return Err(anyhow!(
+                    "The directory of DTA4HANA_CONFIG doesn't exist: {:?}",
+                    parent
+                ));
+            }
+            Ok(config_file)
+        }
+        _ => find_default_config_file(),
+    }
+}
+
 /// Get the default path for storing user credential as a file
 /// It assumes you have write permission in your home dir
 fn find_default_config_file() -> Result<PathBuf, Error> {
@@ -72,9 +1109,240 @@ fn find_default_config_file() -> Result<PathBuf, Error> {
     }
 }
 
+/// Directory of the config file, it is used as the work directory by default
+/// * config_file: path to the config file
+fn default_work_dir(config_file: &Path) -> PathBuf {
+    match config_file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{dta_app, find_default_config_file, twitter_client::TwitterClient};
+    use crate::cli::CommandLineArgs;
+    use crate::{
+        dta_app, exit_code, find_default_config_file, period_of, resolve_app_credential,
+        resolve_app_credential_with_source, resolve_config_file,
+        twitter_client::{AgentOptions, TwitterClient},
+        EXIT_NOTHING_TO_DELETE,
+    };
+    use dta4hana::credential_store::FileStore;
+    use dta4hana::dta_app::{CredentialSource, DeletionSummary};
+    use dta4hana::rate_limiter::Endpoint;
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+    use std::process::ExitCode;
+    use structopt::StructOpt;
+
+    #[test]
+    fn resolve_app_credential_precedence() {
+        let env_name = "DTA4HANA_TEST_RESOLVE_APP_CREDENTIAL";
+        std::env::set_var(env_name, "runtime");
+        let resolved =
+            resolve_app_credential(Some("cli".to_string()), env_name, None, Some("build"));
+        assert_eq!(resolved.unwrap(), "cli");
+        let resolved = resolve_app_credential(None, env_name, Some("file"), Some("build"));
+        assert_eq!(resolved.unwrap(), "runtime");
+        let resolved = resolve_app_credential_with_source(None, env_name, None, Some("build"));
+        assert_eq!(resolved.unwrap().1, CredentialSource::RuntimeEnv);
+
+        std::env::remove_var(env_name);
+        // 設定ファイルの値はビルド時の値より優先する
+        let resolved = resolve_app_credential(None, env_name, Some("file"), Some("build"));
+        assert_eq!(resolved.unwrap(), "file");
+        let resolved = resolve_app_credential(None, env_name, None, Some("build"));
+        assert_eq!(resolved.unwrap(), "build");
+        let resolved = resolve_app_credential_with_source(None, env_name, None, Some("build"));
+        assert_eq!(resolved.unwrap().1, CredentialSource::BuildEnv);
+        let resolved = resolve_app_credential(None, env_name, None, None);
+        assert!(resolved.is_err());
+    }
+
+    #[test]
+    fn quiet_conflicts_with_verbose() {
+        let args = CommandLineArgs::from_iter_safe(["dta4hana", "--quiet", "count"]).unwrap();
+        assert!(args.quiet);
+        assert!(CommandLineArgs::from_iter_safe(["dta4hana", "-q", "-v", "count"]).is_err());
+    }
+
+    #[test]
+    fn oauth_token_requires_secret() {
+        let args = CommandLineArgs::from_iter_safe([
+            "dta4hana",
+            "--oauth-token",
+            "token",
+            "--oauth-token-secret",
+            "secret",
+            "count",
+        ])
+        .unwrap();
+        assert_eq!(args.oauth_token.as_deref(), Some("token"));
+        assert!(
+            CommandLineArgs::from_iter_safe(["dta4hana", "--oauth-token", "token", "count"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn repeated_rate_limits() {
+        let args = CommandLineArgs::from_iter_safe([
+            "dta4hana",
+            "--rate-limit",
+            "delete_tweet=30",
+            "--rate-limit",
+            "timeline=0",
+            "count",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.rate_limits,
+            vec![(Endpoint::DeleteTweet, 30), (Endpoint::Timeline, 0)]
+        );
+        assert!(
+            CommandLineArgs::from_iter_safe(["dta4hana", "--rate-limit", "tweet=1", "count"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn no_backup_conflicts_with_backup() {
+        let args = ["dta4hana", "delete", "--no-backup"];
+        assert!(CommandLineArgs::from_iter_safe(args).is_ok());
+        let args = [
+            "dta4hana",
+            "delete",
+            "--no-backup",
+            "--backup",
+            "backup.jsonl",
+        ];
+        assert!(CommandLineArgs::from_iter_safe(args).is_err());
+        let args = [
+            "dta4hana",
+            "purge",
+            "--no-backup",
+            "--output-dir",
+            "backups",
+        ];
+        assert!(CommandLineArgs::from_iter_safe(args).is_err());
+    }
+
+    #[test]
+    fn fail_fast_with_default_max_failures() {
+        let args = ["dta4hana", "delete", "--fail-fast"];
+        assert!(CommandLineArgs::from_iter_safe(args).is_ok());
+        let args = ["dta4hana", "delete", "--fail-fast", "--max-failures", "3"];
+        assert!(CommandLineArgs::from_iter_safe(args).is_err());
+    }
+
+    #[test]
+    fn invalid_period_of_actions() {
+        let period = |args: &[&str]| {
+            let args = CommandLineArgs::from_iter_safe(args).unwrap();
+            period_of(&args.action)
+                .and_then(|(since, until)| dta_app::validate_period(&since, &until))
+        };
+        assert!(period(&["dta4hana", "delete", "--since", "garbage"]).is_err());
+        assert!(period(&["dta4hana", "fetch", "--until", "2022-02-30"]).is_err());
+        assert!(period(&[
+            "dta4hana",
+            "purge",
+            "--since",
+            "2999-01-01",
+            "--older-than",
+            "1y"
+        ])
+        .is_err());
+        assert!(period(&["dta4hana", "count", "--since", "2022-01-01"]).is_ok());
+        assert!(period(&["dta4hana", "doctor"]).is_ok());
+    }
+
+    #[test]
+    fn given_ids_conflict_with_filters() {
+        let from_stdin = ["dta4hana", "-y", "delete", "--from-stdin"];
+        let from_csv = ["dta4hana", "-y", "delete", "--from-csv", "ids.csv"];
+        assert!(CommandLineArgs::from_iter_safe(from_stdin).is_ok());
+        assert!(CommandLineArgs::from_iter_safe(from_csv).is_ok());
+        let both = [&from_csv[..], &["--from-stdin"]].concat();
+        assert!(CommandLineArgs::from_iter_safe(both).is_err());
+        for filter in [
+            &["--lang", "ja"][..],
+            &["--not-lang", "en"],
+            &["--delete-und"],
+            &["--unknown-source", "delete"],
+            &["--exclude-media"],
+            &["--concurrency", "2"],
+        ] {
+            for given in [&from_stdin[..], &from_csv[..]] {
+                let args = [given, filter].concat();
+                assert!(
+                    CommandLineArgs::from_iter_safe(args).is_err(),
+                    "{:?}",
+                    filter
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn exit_code_of_summary() {
+        let nothing = DeletionSummary::default();
+        assert_eq!(exit_code(&nothing), ExitCode::from(EXIT_NOTHING_TO_DELETE));
+        let deleted = DeletionSummary {
+            attempted: 1,
+            deleted: 1,
+            ..Default::default()
+        };
+        assert_eq!(exit_code(&deleted), ExitCode::SUCCESS);
+        // 削除に失敗しても, 対象があればnothing to deleteではない
+        let failed = DeletionSummary {
+            attempted: 1,
+            skipped: 1,
+            ..Default::default()
+        };
+        assert_eq!(exit_code(&failed), ExitCode::FAILURE);
+        let partially_failed = DeletionSummary {
+            attempted: 2,
+            deleted: 1,
+            skipped: 1,
+            ..Default::default()
+        };
+        assert_eq!(exit_code(&partially_failed), ExitCode::SUCCESS);
+    }
+
+    #[test]
+    fn summary_json_is_one_object() {
+        let summary = DeletionSummary {
+            attempted: 3,
+            deleted: 2,
+            skipped: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&summary).unwrap(),
+            r#"{"attempted":3,"deleted":2,"skipped":1,"matched":0,"not_found":0,"timed_out":false}"#
+        );
+        let args =
+            CommandLineArgs::from_iter_safe(["dta4hana", "--summary-json", "unlike"]).unwrap();
+        assert!(args.summary_json);
+    }
+
+    #[test]
+    fn resolve_config_file_precedence() {
+        let cli = Some(PathBuf::from("cli.json"));
+        let env_dir = std::env::temp_dir();
+        let env = Some(env_dir.join("env.json").into_os_string());
+
+        let resolved = resolve_config_file(cli, env.clone()).unwrap();
+        assert_eq!(resolved, PathBuf::from("cli.json"));
+        let resolved = resolve_config_file(None, env).unwrap();
+        assert_eq!(resolved, env_dir.join("env.json"));
+        let resolved = resolve_config_file(None, Some(OsString::new())).unwrap();
+        assert_eq!(resolved, find_default_config_file().unwrap());
+
+        let missing_dir = env_dir.join("dta4hana-missing-dir").join("env.json");
+        assert!(resolve_config_file(None, Some(missing_dir.into_os_string())).is_err());
+    }
 
     #[test]
     #[ignore]
@@ -96,11 +1364,17 @@ mod tests {
             api_key,
             consumer_key,
             consumer_secret,
-            &find_default_config_file().unwrap(),
+            &FileStore::new(
+                &find_default_config_file().unwrap(),
+                dta_app::DEFAULT_PROFILE,
+                None,
+            ),
+            &AgentOptions::default(),
+            true,
         )
         .unwrap();
-        let result = dta_app::delete_tweets(&tw_client, None, None);
-        assert_eq!(result.is_ok(), true);
+        let result = dta_app::delete_tweets(&tw_client, &dta_app::DeleteOptions::default());
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -123,10 +1397,16 @@ mod tests {
             api_key,
             consumer_key,
             consumer_secret,
-            &find_default_config_file().unwrap(),
+            &FileStore::new(
+                &find_default_config_file().unwrap(),
+                dta_app::DEFAULT_PROFILE,
+                None,
+            ),
+            &AgentOptions::default(),
+            true,
         )
         .unwrap();
-        let result = dta_app::unlike_likes(&tw_client);
-        assert_eq!(result.is_ok(), true);
+        let result = dta_app::unlike_likes(&tw_client, &dta_app::UnlikeOptions::default());
+        assert!(result.is_ok());
     }
 }
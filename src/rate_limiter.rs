@@ -0,0 +1,293 @@
+//! Client-side rate limiter shared by all the requests of [`crate::twitter_client::TwitterClient`]
+//! Each endpoint has a token bucket sized by the known limit of Twitter per 15 minutes,
+//! so the fetch and the delete workers wait before the request instead of hitting 429
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use log::debug;
+
+/// Window of the rate limits of Twitter
+pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+/// Endpoint which has its own rate limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    DeleteTweet,
+    Unlike,
+    DeleteBookmark,
+    DeleteDm,
+    DeleteScheduled,
+    Timeline,
+    LookupTweets,
+    Likes,
+    Bookmarks,
+    DmEvents,
+    Scheduled,
+    Me,
+    LookupUser,
+}
+
+impl Endpoint {
+    pub const ALL: [Endpoint; 13] = [
+        Endpoint::DeleteTweet,
+        Endpoint::Unlike,
+        Endpoint::DeleteBookmark,
+        Endpoint::DeleteDm,
+        Endpoint::DeleteScheduled,
+        Endpoint::Timeline,
+        Endpoint::LookupTweets,
+        Endpoint::Likes,
+        Endpoint::Bookmarks,
+        Endpoint::DmEvents,
+        Endpoint::Scheduled,
+        Endpoint::Me,
+        Endpoint::LookupUser,
+    ];
+
+    /// Name of the endpoint for `--rate-limit`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Endpoint::DeleteTweet => "delete_tweet",
+            Endpoint::Unlike => "unlike",
+            Endpoint::DeleteBookmark => "delete_bookmark",
+            Endpoint::DeleteDm => "delete_dm",
+            Endpoint::DeleteScheduled => "delete_scheduled",
+            Endpoint::Timeline => "timeline",
+            Endpoint::LookupTweets => "lookup_tweets",
+            Endpoint::Likes => "likes",
+            Endpoint::Bookmarks => "bookmarks",
+            Endpoint::DmEvents => "dm_events",
+            Endpoint::Scheduled => "scheduled",
+            Endpoint::Me => "me",
+            Endpoint::LookupUser => "lookup_user",
+        }
+    }
+
+    /// Known limit of the user context per [`RATE_LIMIT_WINDOW`]
+    /// ref: <https://developer.twitter.com/en/docs/twitter-api/rate-limits>
+    pub fn default_quota(&self) -> u32 {
+        match self {
+            Endpoint::DeleteTweet => 50,
+            Endpoint::Unlike => 50,
+            Endpoint::DeleteBookmark => 50,
+            Endpoint::DeleteDm => 300,
+            Endpoint::DeleteScheduled => 300,
+            Endpoint::Timeline => 900,
+            Endpoint::LookupTweets => 900,
+            Endpoint::Likes => 75,
+            Endpoint::Bookmarks => 180,
+            Endpoint::DmEvents => 300,
+            Endpoint::Scheduled => 300,
+            Endpoint::Me => 75,
+            Endpoint::LookupUser => 900,
+        }
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Endpoint::ALL.iter().find(|endpoint| endpoint.name() == s) {
+            Some(endpoint) => Ok(*endpoint),
+            None => Err(anyhow::anyhow!(
+                "Unknown endpoint {:?}, it should be one of {}",
+                s,
+                Endpoint::ALL.map(|endpoint| endpoint.name()).join(", ")
+            )),
+        }
+    }
+}
+
+/// Parse the override of the quota e.g. `delete_tweet=30`
+/// 0 means no limit for the endpoint
+pub fn parse_quota(s: &str) -> Result<(Endpoint, u32)> {
+    let (endpoint, quota) = match s.split_once('=') {
+        Some(pair) => pair,
+        None => {
+            return Err(anyhow::anyhow!(
+                "Rate limit should be <endpoint>=<requests per 15 minutes>: {:?}",
+                s
+            ))
+        }
+    };
+    let quota = match quota.trim().parse() {
+        Ok(quota) => quota,
+        Err(e) => return Err(anyhow::anyhow!("Quota is not valid: {:?}, {}", quota, e)),
+    };
+    Ok((endpoint.trim().parse()?, quota))
+}
+
+/// Clock of [`RateLimiter`], the tests can replace it with a fake one
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+/// [`Clock`] of the system
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        sleep(duration)
+    }
+}
+
+/// Token bucket of an endpoint
+/// The tokens can be negative, it is the debt of the requests waiting for the refill
+struct Bucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(quota: u32, now: Instant) -> Self {
+        Bucket {
+            capacity: quota as f64,
+            refill_per_sec: quota as f64 / RATE_LIMIT_WINDOW.as_secs_f64(),
+            tokens: quota as f64,
+            updated_at: now,
+        }
+    }
+
+    /// Take a token, it returns how long to wait until the token is refilled
+    fn take(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated_at = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.refill_per_sec)
+        }
+    }
+}
+
+/// Token buckets keyed by [`Endpoint`]
+/// It is shared by the workers, so the combined rate of all the requests stays under the limits
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<Endpoint, Bucket>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RateLimiter {
+    /// Constructs new limiter with the system clock
+    /// * overrides: quota per [`RATE_LIMIT_WINDOW`] which replaces [`Endpoint::default_quota()`], 0 means no limit
+    pub fn new(overrides: &[(Endpoint, u32)]) -> Self {
+        RateLimiter::with_clock(overrides, Arc::new(SystemClock))
+    }
+
+    /// [`RateLimiter::new()`] with the given clock
+    /// * overrides: see [`RateLimiter::new()`]
+    /// * clock: e.g. the fake clock of the tests
+    pub fn with_clock(overrides: &[(Endpoint, u32)], clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        let buckets = Endpoint::ALL
+            .iter()
+            .filter_map(|endpoint| {
+                let quota = match overrides.iter().rev().find(|(each, _)| each == endpoint) {
+                    Some((_, quota)) => *quota,
+                    None => endpoint.default_quota(),
+                };
+                // 0は制限なし
+                (quota > 0).then(|| (*endpoint, Bucket::new(quota, now)))
+            })
+            .collect();
+        RateLimiter {
+            buckets: Mutex::new(buckets),
+            clock,
+        }
+    }
+
+    /// Wait until the request to the endpoint is allowed
+    /// The token is reserved before waiting, so the waiting workers are served in order
+    /// * endpoint: endpoint of the request
+    pub fn acquire(&self, endpoint: Endpoint) {
+        let wait = {
+            let mut buckets = self.buckets.lock().unwrap();
+            match buckets.get_mut(&endpoint) {
+                Some(bucket) => bucket.take(self.clock.now()),
+                None => Duration::ZERO,
+            }
+        };
+        if !wait.is_zero() {
+            debug!(
+                "Rate limit of {} is reached, waiting for {:.1}s.",
+                endpoint.name(),
+                wait.as_secs_f64()
+            );
+            self.clock.sleep(wait);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use crate::rate_limiter::{parse_quota, Clock, Endpoint, RateLimiter};
+
+    /// Clock which advances only by the sleeps
+    struct FakeClock {
+        now: Mutex<Instant>,
+        slept: Mutex<Vec<Duration>>,
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            *self.now.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            *self.now.lock().unwrap() += duration;
+            self.slept.lock().unwrap().push(duration);
+        }
+    }
+
+    #[test]
+    fn acquire_with_fake_clock() {
+        let clock = Arc::new(FakeClock {
+            now: Mutex::new(Instant::now()),
+            slept: Mutex::new(vec![]),
+        });
+        // 15分に3回, 1回あたり5分で回復する
+        let limiter = RateLimiter::with_clock(
+            &[(Endpoint::DeleteTweet, 3), (Endpoint::Timeline, 0)],
+            clock.clone(),
+        );
+        for _ in 0..4 {
+            limiter.acquire(Endpoint::DeleteTweet);
+        }
+        // 制限なしのエンドポイントは待たない
+        for _ in 0..1000 {
+            limiter.acquire(Endpoint::Timeline);
+        }
+        assert_eq!(
+            *clock.slept.lock().unwrap(),
+            vec![Duration::from_secs(5 * 60)]
+        );
+    }
+
+    #[test]
+    fn parse_quota_of_endpoint() {
+        assert_eq!(
+            parse_quota("delete_tweet=30").unwrap(),
+            (Endpoint::DeleteTweet, 30)
+        );
+        assert!(parse_quota("delete_tweet").is_err());
+        assert!(parse_quota("unknown=30").is_err());
+        assert!(parse_quota("likes=-1").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Retry with the exponential backoff and the random jitter
+//! All the requests of [`crate::twitter_client::TwitterClient`] except the OAuth 2.0 login are retried by [`RetryPolicy`]
+use std::thread::sleep;
+use std::time::Duration;
+
+use log::warn;
+use rand::Rng;
+
+use crate::http::HttpError;
+
+/// Default number of the retries on the network errors
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+/// Default delay before the first retry, it is doubled on each retry
+pub const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Default upper limit of the delay before the retry
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(30);
+/// Default upper limit of the random jitter, the half of [`DEFAULT_BASE_DELAY`]
+pub const DEFAULT_JITTER: Duration = Duration::from_millis(250);
+/// HTTP status which is retried like the network errors
+const SERVICE_UNAVAILABLE: u16 = 503;
+
+/// Error which can be retried, see [`RetryPolicy::retry()`]
+pub trait Retryable {
+    /// Why it should be retried for the log, None if it should not be retried
+    fn retry_reason(&self) -> Option<String>;
+}
+
+/// The network errors e.g. connection reset and 503 are retried, the other statuses are returned as they are
+impl Retryable for HttpError {
+    fn retry_reason(&self) -> Option<String> {
+        match self {
+            HttpError::Transport(transport) => Some(format!("Request was failed: {}", transport)),
+            HttpError::Status(SERVICE_UNAVAILABLE, _) => Some(String::from("Service unavailable")),
+            HttpError::Status(_, _) => None,
+        }
+    }
+}
+
+/// How to retry the operation
+/// * max_retries: how many times it is retried, 0 means no retry
+/// * base_delay: the delay before the first retry, it is doubled on each retry
+/// * max_delay: upper limit of the delay, the jitter is added after the limit
+/// * jitter: random 0..jitter is added to each delay for avoiding the retries at the same time
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            jitter: DEFAULT_JITTER,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Run the operation and retry it while the error is [`Retryable`]
+    /// The last error is returned if it is not retryable or the retries are exhausted
+    /// * op: the operation, it is called on each attempt e.g. for signing the request again
+    pub fn retry<T, E: Retryable>(&self, op: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+        self.retry_with_sleep(sleep, op)
+    }
+
+    /// [`RetryPolicy::retry()`] with the given sleep, the tests can replace the clock
+    /// * sleep: wait for the delay before each retry
+    /// * op: the operation, it is called on each attempt
+    pub fn retry_with_sleep<T, E: Retryable>(
+        &self,
+        mut sleep: impl FnMut(Duration),
+        mut op: impl FnMut() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut attempt = 0;
+        loop {
+            let error = match op() {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            let reason = match error.retry_reason() {
+                Some(reason) if attempt < self.max_retries => reason,
+                _ => return Err(error),
+            };
+            let delay = self.delay(attempt, self.random_jitter());
+            attempt += 1;
+            warn!(
+                "{}, retrying in {:.1}s. ({} / {})",
+                reason,
+                delay.as_secs_f64(),
+                attempt,
+                self.max_retries
+            );
+            sleep(delay);
+        }
+    }
+
+    /// Delay before the retry, it is doubled on each attempt and capped by `max_delay`
+    /// * attempt: how many times it was retried so far
+    /// * jitter: added to the delay after the cap
+    pub fn delay(&self, attempt: u32, jitter: Duration) -> Duration {
+        let exponential = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        exponential.min(self.max_delay) + jitter
+    }
+
+    /// Random jitter up to `jitter`
+    fn random_jitter(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return Duration::ZERO;
+        }
+        rand::thread_rng().gen_range(Duration::ZERO..=self.jitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::http::{HttpError, HttpResponse};
+    use crate::retry::RetryPolicy;
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: Duration::ZERO,
+        }
+    }
+
+    fn status(code: u16) -> HttpError {
+        HttpError::Status(
+            code,
+            HttpResponse {
+                status: code,
+                headers: vec![],
+                body: String::new(),
+            },
+        )
+    }
+
+    #[test]
+    fn delay_grows_exponentially() {
+        let policy = policy(3);
+        assert_eq!(policy.delay(0, Duration::ZERO), Duration::from_millis(500));
+        assert_eq!(
+            policy.delay(2, Duration::from_millis(10)),
+            Duration::from_millis(2010)
+        );
+        assert_eq!(policy.delay(20, Duration::ZERO), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn retry_with_fake_clock() {
+        let mut slept = vec![];
+        let mut errors = vec![status(503), HttpError::Transport("reset".to_string())];
+        let result = policy(2).retry_with_sleep(
+            |delay| slept.push(delay),
+            || match errors.pop() {
+                Some(error) => Err(error),
+                None => Ok("done"),
+            },
+        );
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(
+            slept,
+            vec![Duration::from_millis(500), Duration::from_millis(1000)]
+        );
+
+        // リトライできないステータスは, そのまま返す
+        let mut attempts = 0;
+        let result: Result<(), HttpError> = policy(2).retry_with_sleep(
+            |_| {},
+            || {
+                attempts += 1;
+                Err(status(404))
+            },
+        );
+        assert!(matches!(result, Err(HttpError::Status(404, _))));
+        assert_eq!(attempts, 1);
+
+        // リトライ上限を超えたら, 最後のエラーを返す
+        let mut attempts = 0;
+        let result: Result<(), HttpError> = policy(1).retry_with_sleep(
+            |_| {},
+            || {
+                attempts += 1;
+                Err(HttpError::Transport("reset".to_string()))
+            },
+        );
+        assert!(matches!(result, Err(HttpError::Transport(_))));
+        assert_eq!(attempts, 2);
+    }
+}
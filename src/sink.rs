@@ -0,0 +1,210 @@
+//! Output sinks of the exported tweets, see [`crate::dta_app::fetch_tweets()`]
+//! Each sink writes the tweets one by one as they are fetched, so the fetch doesn't depend on the format
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::twitter_object::Tweet;
+
+/// Output of the exported tweets in a format
+pub trait TweetSink {
+    /// Write the tweet
+    /// * tweet: fetched tweet
+    fn write(&mut self, tweet: &Tweet) -> Result<()>;
+
+    /// Write the rest of the output e.g. the end of the array, and flush it
+    fn finish(self) -> Result<()>;
+}
+
+/// JSON array of the tweets, it is a valid JSON only after [`TweetSink::finish()`]
+pub struct JsonArraySink<W: Write> {
+    writer: W,
+    count: usize,
+}
+
+impl<W: Write> JsonArraySink<W> {
+    /// Constructs new sink
+    /// * writer: output e.g. the file or stdout
+    pub fn new(writer: W) -> Self {
+        JsonArraySink { writer, count: 0 }
+    }
+}
+
+impl<W: Write> TweetSink for JsonArraySink<W> {
+    fn write(&mut self, tweet: &Tweet) -> Result<()> {
+        let separator: &[u8] = if self.count == 0 { b"[" } else { b"," };
+        self.writer.write_all(separator)?;
+        serde_json::to_writer(&mut self.writer, tweet)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        // 1件もなければ空の配列
+        let end: &[u8] = if self.count == 0 { b"[]" } else { b"]" };
+        self.writer.write_all(end)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// One JSON object per line, each line is flushed so that the output is valid even if it is aborted later
+pub struct JsonlSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonlSink<W> {
+    /// Constructs new sink
+    /// * writer: output e.g. the file or stdout
+    pub fn new(writer: W) -> Self {
+        JsonlSink { writer }
+    }
+}
+
+impl<W: Write> TweetSink for JsonlSink<W> {
+    fn write(&mut self, tweet: &Tweet) -> Result<()> {
+        let mut line = serde_json::to_vec(tweet)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Header of [`CsvSink`]
+pub const CSV_HEADER: [&str; 9] = [
+    "id",
+    "created_at",
+    "text",
+    "retweet_count",
+    "reply_count",
+    "like_count",
+    "quote_count",
+    "lang",
+    "source",
+];
+
+/// CSV with the header of [`CSV_HEADER`], e.g. for the spreadsheets
+/// The other fields requested with `tweet.fields` are not written, use JSON for them
+pub struct CsvSink<W: Write> {
+    writer: csv::Writer<W>,
+    header_written: bool,
+}
+
+impl<W: Write> CsvSink<W> {
+    /// Constructs new sink
+    /// * writer: output e.g. the file or stdout
+    pub fn new(writer: W) -> Self {
+        CsvSink {
+            writer: csv::Writer::from_writer(writer),
+            header_written: false,
+        }
+    }
+
+    /// Write the header once, it is written even if there are no tweets
+    fn write_header(&mut self) -> Result<()> {
+        if !self.header_written {
+            self.writer.write_record(CSV_HEADER)?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> TweetSink for CsvSink<W> {
+    fn write(&mut self, tweet: &Tweet) -> Result<()> {
+        self.write_header()?;
+        let metrics = &tweet.public_metrics;
+        self.writer.write_record([
+            tweet.id.as_str(),
+            tweet.created_at.as_str(),
+            tweet.text.as_str(),
+            &metrics.retweet_count.to_string(),
+            &metrics.reply_count.to_string(),
+            &metrics.like_count.to_string(),
+            &metrics.quote_count.to_string(),
+            tweet.lang.as_deref().unwrap_or_default(),
+            tweet.source.as_deref().unwrap_or_default(),
+        ])?;
+        // 中断されても, 書き込み済みの行を残す
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.write_header()?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sink::{CsvSink, JsonArraySink, JsonlSink, TweetSink};
+    use crate::twitter_object::Tweet;
+
+    fn tweet() -> Tweet {
+        serde_json::from_str(
+            r#"{"id":"1","created_at":"2022-01-01T00:00:00.000Z","text":"Hello, \"world\"","public_metrics":{"retweet_count":1,"reply_count":2,"like_count":3,"quote_count":4},"lang":"en"}"#,
+        )
+        .unwrap()
+    }
+
+    /// Write the tweet twice and finish the sink
+    fn write_twice(mut sink: impl TweetSink) {
+        sink.write(&tweet()).unwrap();
+        sink.write(&tweet()).unwrap();
+        sink.finish().unwrap();
+    }
+
+    const TWEET_JSON: &str = r#"{"id":"1","created_at":"2022-01-01T00:00:00.000Z","text":"Hello, \"world\"","public_metrics":{"retweet_count":1,"reply_count":2,"like_count":3,"quote_count":4},"attachments":null,"in_reply_to_user_id":null,"referenced_tweets":[],"lang":"en"}"#;
+
+    #[test]
+    fn write_json_array() {
+        let mut output = Vec::new();
+        write_twice(JsonArraySink::new(&mut output));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("[{},{}]", TWEET_JSON, TWEET_JSON)
+        );
+
+        // 1件もなくても, 有効なJSON
+        let mut output = Vec::new();
+        JsonArraySink::new(&mut output).finish().unwrap();
+        assert_eq!(output, b"[]");
+    }
+
+    #[test]
+    fn write_json_lines() {
+        let mut output = Vec::new();
+        write_twice(JsonlSink::new(&mut output));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{}\n{}\n", TWEET_JSON, TWEET_JSON)
+        );
+    }
+
+    #[test]
+    fn write_csv() {
+        let mut output = Vec::new();
+        write_twice(CsvSink::new(&mut output));
+        let row = r#"1,2022-01-01T00:00:00.000Z,"Hello, ""world""",1,2,3,4,en,"#;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "id,created_at,text,retweet_count,reply_count,like_count,quote_count,lang,source\n{}\n{}\n",
+                row, row
+            )
+        );
+
+        // 1件もなくても, ヘッダーは書く
+        let mut output = Vec::new();
+        CsvSink::new(&mut output).finish().unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("id,"));
+    }
+}
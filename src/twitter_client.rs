@@ -3,44 +3,318 @@
 //! Define it as trait and implement it for the testability(using mock)
 use std::{
     collections::{BTreeMap, HashMap},
+    io::BufRead,
+    str::FromStr,
+    sync::{Arc, Mutex},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::Result;
-use log::{debug, info};
+use log::{debug, info, warn};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use serde::{Deserialize, Serialize};
-use std::env;
 use url::Url;
 use uuid::Uuid;
 
-use crate::twitter_object::{ResponseObject, Tweet, User};
+use crate::http::{HttpError, HttpExecutor, HttpRequest, HttpResponse, UreqExecutor};
+use crate::rate_limiter::{Endpoint, RateLimiter};
+use crate::retry::RetryPolicy;
+use crate::twitter_object::{
+    Deleted, DmEvent, ErrorResponse, ResponseLookup, ResponseObject, ResponsePage, ScheduledPage,
+    Tweet, User,
+};
 
 /// Twitter Client
 /// It needs to know the endpoints and all required credentials
 pub struct TwitterClient {
-    agent: ureq::Agent,
+    executor: Box<dyn HttpExecutor>,
     server: Url,
     app_cred: TwitterAppCredential,
     user_cred: Option<TwitterAppUserCredential>,
+    retry_policy: RetryPolicy,
+    /// Client-side limiter of the requests per endpoint, see [`TwitterClient::with_rate_limiter()`]
+    rate_limiter: Arc<RateLimiter>,
+    rate_limit: Mutex<Option<RateLimit>>,
+    /// User id of the timeline to fetch, the logged-in user if it is None
+    timeline_user_id: Option<String>,
+    /// API version of the deletion, see [`TwitterClient::api_version()`]
+    api_version: Option<ApiVersion>,
+    /// Open the authorize URL of the login in the browser, see [`TwitterClient::with_open_browser()`]
+    open_browser: bool,
+    /// Scopes requested by `login --oauth2`, see [`TwitterClient::with_oauth2_scopes()`]
+    oauth2_scopes: String,
+    /// Pinned tweet of the logged-in user, it is set by [`crate::dta_app::init_client()`]
+    pinned_tweet_id: Option<String>,
+    /// `since_id` and `until_id` of the timeline, they are exclusive as the API, see [`TwitterClient::with_id_range()`]
+    since_id: Option<String>,
+    until_id: Option<String>,
+    /// `tweet.fields` of the endpoints which return the tweets, see [`TWEET_FIELDS`]
+    tweet_fields: String,
+    /// `expansions` of the endpoints which return the tweets, no expansion if it is None
+    expansions: Option<String>,
+    /// `max_results` of the timeline and the likes, see [`TwitterClient::with_page_size()`]
+    page_size: u8,
+    /// Sleep before the next page of the timeline and the likes, see [`TwitterClient::with_page_interval()`]
+    page_interval: Duration,
 }
 /// App side credentials
-/// It will be passed in build time and it will not be changed by app users
-#[derive(Debug, Deserialize, Serialize)]
+/// It will be passed in build time or stored in the config file, and it will not be changed by app users
+#[derive(Clone, Deserialize, Serialize)]
 pub struct TwitterAppCredential {
     pub api_key: String,
     pub consumer_key: String,
     pub consumer_secret: String,
 }
+
+/// The secrets are never written to the logs
+impl std::fmt::Debug for TwitterAppCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwitterAppCredential")
+            .field("api_key", &"****")
+            .field("consumer_key", &"****")
+            .field("consumer_secret", &"****")
+            .finish()
+    }
+}
 /// User side credentials
 /// It will be updated after login
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct TwitterAppUserCredential {
     pub username: String,
+    /// It can be empty in the old config file, and it is refreshed by [`crate::dta_app::init_client()`]
+    #[serde(default)]
     pub id: String,
     pub oauth_token: String,
     pub oauth_token_secret: String,
+    /// It is only needed for OAuth 2.0 user context endpoints e.g. bookmarks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth2: Option<TwitterOAuth2Credential>,
+}
+
+/// The tokens are never written to the logs e.g. when they are given by `--oauth-token`
+impl std::fmt::Debug for TwitterAppUserCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwitterAppUserCredential")
+            .field("username", &self.username)
+            .field("id", &self.id)
+            .field("oauth_token", &"****")
+            .field("oauth_token_secret", &"****")
+            .field("oauth2", &self.oauth2.as_ref().map(|_| "****"))
+            .finish()
+    }
+}
+/// Request token of PIN-based authorization, it is the intermediate state between
+/// [`TwitterClient::request_login()`] and [`TwitterClient::complete_login()`]
+/// It can be stored to complete the login in the next invocation e.g. `login --pin`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TwitterRequestToken {
+    pub username: String,
+    pub id: String,
+    pub oauth_token: String,
+    /// URL to authorize the app on your browser, the PIN is shown after that
+    pub authorize_url: String,
+}
+/// User side credentials for OAuth 2.0
+/// It will be stored after `login --oauth2`
+/// * scope: granted scopes separated by space, it is None if it was stored before the scopes were recorded
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TwitterOAuth2Credential {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub scope: Option<String>,
+}
+
+/// Rate limit of the endpoint, it comes from `x-rate-limit-*` headers of the last response
+/// * remaining: how many requests can be sent in the current window
+/// * reset: when the window is reset, in UNIX epoch seconds
+/// * limit: how many requests can be sent in a window, None if the header is missing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub remaining: u32,
+    pub reset: u64,
+    pub limit: Option<u32>,
+}
+
+impl RateLimit {
+    /// Read the rate limit from the response headers, it is None if the remaining or the reset is missing
+    fn from_response(response: &HttpResponse) -> Option<RateLimit> {
+        let remaining = response.header("x-rate-limit-remaining")?.parse().ok()?;
+        let reset = response.header("x-rate-limit-reset")?.parse().ok()?;
+        let limit = response
+            .header("x-rate-limit-limit")
+            .and_then(|limit| limit.parse().ok());
+        Some(RateLimit {
+            remaining,
+            reset,
+            limit,
+        })
+    }
+}
+
+/// Outcome of [`TwitterClientTrait::delete_liked()`]
+/// * Unliked: the tweet was unliked
+/// * AlreadyGone: the tweet was not found(e.g. deleted by its author), so there is nothing to unlike
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnlikeOutcome {
+    Unliked,
+    AlreadyGone,
+}
+
+/// Outcome of [`TwitterClientTrait::delete_tweet()`]
+/// * Deleted: the tweet was deleted
+/// * AlreadyGone: the tweet was not found(e.g. deleted in the previous run), so there is nothing to delete
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeleteOutcome {
+    Deleted,
+    AlreadyGone,
+}
+
+/// API version of [`TwitterClientTrait::delete_tweet()`]
+/// * V1: v1.1 `statuses/destroy` with OAuth 1.0a, it is deprecated by Twitter
+/// * V2: `DELETE /2/tweets/:id` with OAuth 2.0 user context, see [`TwitterClientTrait::login_oauth2()`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl FromStr for ApiVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "v1" => Ok(ApiVersion::V1),
+            "v2" => Ok(ApiVersion::V2),
+            _ => Err(anyhow::anyhow!("Unknown API version: {}", s)),
+        }
+    }
+}
+
+/// Options for the HTTP agent of [`TwitterClient`]
+/// * proxy: proxy URL e.g. `http://proxy.example.com:8080` or `socks5://127.0.0.1:1080`, no proxy if it is None
+/// * timeout: read/write timeout, no timeout if it is None
+/// * connect_timeout: connect timeout, no timeout if it is None
+/// * retry_policy: how the request is retried on the network errors e.g. connection reset, and 503
+/// * rate_limits: requests per 15 minutes which override the known limits of the endpoints, see [`RateLimiter::new()`]
+/// * user_agent: User-Agent header of all the requests, see [`DEFAULT_USER_AGENT`]
+/// * wait_until_available: how long the API is polled before the action during an outage, no poll if it is None
+///   see [`crate::dta_app::wait_until_available()`]
+#[derive(Debug)]
+pub struct AgentOptions {
+    pub proxy: Option<String>,
+    pub timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub retry_policy: RetryPolicy,
+    pub rate_limits: Vec<(Endpoint, u32)>,
+    pub user_agent: String,
+    pub wait_until_available: Option<Duration>,
+}
+
+impl Default for AgentOptions {
+    fn default() -> Self {
+        AgentOptions {
+            proxy: None,
+            timeout: Some(Duration::from_secs(DEFAULT_TIMEOUT_SECS)),
+            connect_timeout: Some(Duration::from_secs(DEFAULT_TIMEOUT_SECS)),
+            retry_policy: RetryPolicy::default(),
+            rate_limits: vec![],
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            wait_until_available: None,
+        }
+    }
 }
 
+/// Default timeout of the requests
+pub const DEFAULT_TIMEOUT_SECS: u64 = 5;
+/// Default User-Agent, the version is embedded at the build time
+pub const DEFAULT_USER_AGENT: &str = concat!("dta4hana/", env!("CARGO_PKG_VERSION"));
+
+/// Default `tweet.fields` for fetching the tweets, they are deserialized into [`Tweet`]
+/// It is used by all the endpoints which return the tweets e.g. timeline, likes and bookmarks
+const TWEET_FIELDS: &str =
+    "created_at,public_metrics,attachments,text,referenced_tweets,in_reply_to_user_id,source,lang,possibly_sensitive,withheld";
+/// `tweet.fields` which are always requested, because the actions depend on them e.g. the period filter
+const REQUIRED_TWEET_FIELDS: [&str; 1] = ["created_at"];
+/// Known `tweet.fields`, the unknown ones are warned but requested as they are
+/// ref: <https://developer.twitter.com/en/docs/twitter-api/data-dictionary/object-model/tweet>
+const KNOWN_TWEET_FIELDS: [&str; 22] = [
+    "attachments",
+    "author_id",
+    "context_annotations",
+    "conversation_id",
+    "created_at",
+    "edit_controls",
+    "edit_history_tweet_ids",
+    "entities",
+    "geo",
+    "id",
+    "in_reply_to_user_id",
+    "lang",
+    "non_public_metrics",
+    "note_tweet",
+    "organic_metrics",
+    "possibly_sensitive",
+    "promoted_metrics",
+    "public_metrics",
+    "referenced_tweets",
+    "reply_settings",
+    "source",
+    "withheld",
+];
+/// `media.fields` for the expanded media, they are deserialized into [`crate::twitter_object::Media`]
+const MEDIA_FIELDS: &str = "url,preview_image_url,variants";
+/// Known `expansions` of the tweets, the unknown ones are warned but requested as they are
+/// ref: <https://developer.twitter.com/en/docs/twitter-api/expansions>
+const KNOWN_EXPANSIONS: [&str; 9] = [
+    "attachments.media_keys",
+    "attachments.poll_ids",
+    "author_id",
+    "edit_history_tweet_ids",
+    "entities.mentions.username",
+    "geo.place_id",
+    "in_reply_to_user_id",
+    "referenced_tweets.id",
+    "referenced_tweets.id.author_id",
+];
+/// `user.fields` for fetching the users, they are deserialized into [`User`]
+const USER_FIELDS: &str = "verified,protected,pinned_tweet_id";
+/// `dm_event.fields` for fetching the direct message events, they are deserialized into [`DmEvent`]
+const DM_EVENT_FIELDS: &str = "id,event_type,created_at,sender_id,text";
+
+/// Base URL of Twitter API, see [`TwitterClient::server()`]
+pub const API_URL: &str = "https://api.twitter.com";
+/// Twitter Ads API, the scheduled tweets are only available on there
+const ADS_API_URL: &str = "https://ads-api.twitter.com/12/";
+/// Max `count` of the scheduled tweets per page
+const SCHEDULED_TWEETS_PER_PAGE: &str = "200";
+/// Range of `max_results` of the timeline, see [`TwitterClient::with_page_size()`]
+pub const MIN_PAGE_SIZE: u8 = 5;
+pub const MAX_PAGE_SIZE: u8 = 100;
+/// Min `max_results` of the likes, it is larger than the timeline
+const LIKES_MIN_PAGE_SIZE: u8 = 10;
+/// Max ids of `GET /2/tweets` per request
+pub const TWEETS_PER_LOOKUP: usize = 100;
+
+/// Authorization endpoint of OAuth 2.0, it is not the same host as APIs
+const OAUTH2_AUTHORIZE_URL: &str = "https://twitter.com/i/oauth2/authorize";
+/// It must be registered as the callback URL of your app
+/// No server is listening on there, the user copies the redirected URL from the browser
+const OAUTH2_REDIRECT_URI: &str = "http://127.0.0.1/callback";
+/// Scopes for the actions which need OAuth 2.0 user context
+const OAUTH2_SCOPES: &str =
+    "tweet.read tweet.write users.read bookmark.read bookmark.write dm.read dm.write offline.access";
+/// Scopes of the v2 deletion, see [`TwitterClient::require_oauth2_scopes()`]
+pub const DELETE_TWEET_SCOPES: &[&str] = &["tweet.read", "tweet.write", "users.read"];
+/// Scopes of the unbookmark, it reads the bookmarks before deleting them
+pub const UNBOOKMARK_SCOPES: &[&str] = &[
+    "tweet.read",
+    "users.read",
+    "bookmark.read",
+    "bookmark.write",
+];
+/// Scopes of the direct message deletion, it reads the events before deleting them
+pub const DELETE_DMS_SCOPES: &[&str] = &["tweet.read", "users.read", "dm.read", "dm.write"];
+
 #[cfg(test)]
 use mockall::{automock, predicate::*};
 #[cfg_attr(test, automock)]
@@ -50,13 +324,41 @@ pub trait TwitterClientTrait {
         consumer_key: String,
         consumer_secret: String,
         user_cred: Option<TwitterAppUserCredential>,
-    ) -> Self;
-    fn delete_liked(&self, tweet_id_str: &str) -> Result<()>;
-    fn delete_tweet(&self, tweet_id_str: &str) -> Result<()>;
+        agent_options: &AgentOptions,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+    fn delete_bookmark(&self, tweet_id_str: &str) -> Result<()>;
+    fn delete_dm_event(&self, event_id: &str) -> Result<()>;
+    fn delete_liked(&self, tweet_id_str: &str) -> Result<UnlikeOutcome>;
+    fn delete_scheduled(&self, account_id: &str, scheduled_tweet_id: &str) -> Result<()>;
+    fn delete_tweet(&self, tweet_id_str: &str) -> Result<DeleteOutcome>;
+    fn download_media(&self, url: &str) -> Result<Vec<u8>>;
+    fn fetch_bookmarks(&self) -> Result<Vec<Tweet>>;
+    fn fetch_dm_events(&self, pagination_token: Option<String>) -> Result<ResponsePage<DmEvent>>;
+    fn fetch_scheduled(&self, account_id: &str, cursor: Option<String>) -> Result<ScheduledPage>;
     fn fetch_timeline(&self, since: Option<String>, until: Option<String>) -> Result<Vec<Tweet>>;
+    fn fetch_timeline_page(
+        &self,
+        since: Option<String>,
+        until: Option<String>,
+        pagination_token: Option<String>,
+    ) -> Result<ResponsePage<Tweet>>;
     fn fetch_likes(&self) -> Result<Vec<Tweet>>;
+    fn fetch_tweets_by_ids(&self, ids: &[String]) -> Result<ResponseLookup<Tweet>>;
+    fn fetch_likes_page(&self, pagination_token: Option<String>) -> Result<ResponsePage<Tweet>>;
+    fn get_me(&self) -> Result<User>;
     fn init_user_cred(self, user_cred: TwitterAppUserCredential) -> Result<TwitterClient>;
     fn login(&self) -> Result<TwitterAppUserCredential>;
+    fn request_login(&self, username: &str) -> Result<TwitterRequestToken>;
+    fn complete_login(
+        &self,
+        request_token: &TwitterRequestToken,
+        pin: &str,
+    ) -> Result<TwitterAppUserCredential>;
+    fn login_oauth2(&self, client_id: &str) -> Result<TwitterOAuth2Credential>;
+    fn lookup_user(&self, username: &str) -> Result<User>;
+    fn rate_limit(&self) -> Option<RateLimit>;
 }
 
 impl TwitterClientTrait for TwitterClient {
@@ -67,20 +369,21 @@ impl TwitterClientTrait for TwitterClient {
     /// * user_cred: It is optional, because this client is also needed in the first time use(i.e. login),
     ///   but you can't call all other apis until you put this credential.
     ///   You can fill this later with [`TwitterClient::init_user_cred()`]
+    /// * agent_options: options for the HTTP agent e.g. proxy
+    ///
+    /// It returns an error if the API endpoint or the proxy is not a valid URL
     fn new(
         api_key: String,
         consumer_key: String,
         consumer_secret: String,
         user_cred: Option<TwitterAppUserCredential>,
-    ) -> Self {
-        let server = match Url::parse("https://api.twitter.com") {
+        agent_options: &AgentOptions,
+    ) -> Result<Self> {
+        let server = match Url::parse(API_URL) {
             Ok(url) => url,
-            Err(_) => panic!("API Endpoints is not valid."),
+            Err(e) => return Err(anyhow::anyhow!("API Endpoints is not valid: {}", e)),
         };
-        let agent: ureq::Agent = ureq::AgentBuilder::new()
-            .timeout_read(Duration::from_secs(5))
-            .timeout_write(Duration::from_secs(5))
-            .build();
+        let executor = UreqExecutor::new(agent_options)?;
 
         let app_cred = TwitterAppCredential {
             api_key,
@@ -88,63 +391,78 @@ impl TwitterClientTrait for TwitterClient {
             consumer_secret,
         };
 
-        TwitterClient {
-            agent,
+        Ok(TwitterClient {
+            executor: Box::new(executor),
             server,
             app_cred,
             user_cred,
-        }
+            retry_policy: agent_options.retry_policy.clone(),
+            rate_limiter: Arc::new(RateLimiter::new(&agent_options.rate_limits)),
+            rate_limit: Mutex::new(None),
+            timeline_user_id: None,
+            api_version: None,
+            open_browser: false,
+            oauth2_scopes: OAUTH2_SCOPES.to_string(),
+            pinned_tweet_id: None,
+            since_id: None,
+            until_id: None,
+            tweet_fields: TWEET_FIELDS.to_string(),
+            expansions: None,
+            page_size: MAX_PAGE_SIZE,
+            page_interval: Duration::ZERO,
+        })
     }
 
-    /// Delete(unliked) your liked tweet from your liked tweets
-    /// * _tweet_id_str: target tweet id
-    fn delete_liked(&self, tweet_id_str: &str) -> Result<()> {
+    /// Delete(unbookmark) the tweet from your bookmarks
+    /// It requires OAuth 2.0 user context, see [`TwitterClient::login_oauth2()`]
+    /// * tweet_id_str: target tweet id
+    fn delete_bookmark(&self, tweet_id_str: &str) -> Result<()> {
         let user_cred = match &self.user_cred {
             Some(cred) => cred,
             None => return Err(anyhow::anyhow!("Credential is not loaded.")),
         };
+        let access_token = self.oauth2_access_token()?;
 
-        let oauth_token = &user_cred.oauth_token;
-        let oauth_token_secret = &user_cred.oauth_token_secret;
-        let consumer_key = &self.app_cred.consumer_key;
-        let consumer_secret = &self.app_cred.consumer_secret;
-
-        let request_url = self.server.join("/1.1/favorites/destroy.json")?;
-        let query_params: Vec<QueryParam> = vec![QueryParam::new("id", tweet_id_str)];
+        let request_url = self.server.join(&format!(
+            "2/users/{}/bookmarks/{}",
+            &user_cred.id, tweet_id_str
+        ))?;
 
-        // https://rust-lang-nursery.github.io/rust-cookbook/encoding/strings.html#percent-encode-a-string
-        let request_method = &String::from("POST");
+        let delete_bookmark_response = self.call_with_retry(Endpoint::DeleteBookmark, || {
+            HttpRequest::new("DELETE", request_url.clone())
+                .header("Authorization", &format!("Bearer {}", access_token))
+        });
 
-        let oauth_signature = build_oauth_signature(
-            oauth_token,
-            oauth_token_secret,
-            consumer_key,
-            consumer_secret,
-            request_url.clone(),
-            request_method,
-            query_params.clone(),
-        );
+        match delete_bookmark_response {
+            Ok(_) => Ok(()),
+            Err(e) => Err(describe_error("Failed to unbookmark.", e)),
+        }
+    }
 
-        let mut signed_unlike_tweet_request = self
-            .agent
-            .request_url(request_method.as_str(), &request_url)
-            .set("Authorization", &oauth_signature);
+    /// Delete the direct message event
+    /// It requires OAuth 2.0 user context with `dm.write`, see [`TwitterClient::login_oauth2()`]
+    /// * event_id: target event id
+    fn delete_dm_event(&self, event_id: &str) -> Result<()> {
+        let access_token = self.oauth2_access_token()?;
 
-        for each in query_params {
-            signed_unlike_tweet_request = signed_unlike_tweet_request.query(&each.key, &each.value);
-        }
+        let request_url = self.server.join(&format!("2/dm_events/{}", event_id))?;
 
-        let signed_unlike_tweet_response = signed_unlike_tweet_request.call();
+        let delete_dm_event_response = self.call_with_retry(Endpoint::DeleteDm, || {
+            HttpRequest::new("DELETE", request_url.clone())
+                .header("Authorization", &format!("Bearer {}", access_token))
+        });
 
-        match signed_unlike_tweet_response {
+        match delete_dm_event_response {
             Ok(_) => Ok(()),
-            Err(_) => Err(anyhow::anyhow!("Failed to unlike.")),
+            Err(e) => Err(describe_error("Failed to delete the direct message.", e)),
         }
     }
 
-    /// Delete your liked tweet
+    /// Delete(unliked) your liked tweet from your liked tweets
     /// * tweet_id_str: target tweet id
-    fn delete_tweet(&self, tweet_id_str: &str) -> Result<()> {
+    ///
+    /// 404 is not an error but [`UnlikeOutcome::AlreadyGone`], because the liked tweet can be deleted by its author
+    fn delete_liked(&self, tweet_id_str: &str) -> Result<UnlikeOutcome> {
         let user_cred = match &self.user_cred {
             Some(cred) => cred,
             None => return Err(anyhow::anyhow!("Credential is not loaded.")),
@@ -155,38 +473,100 @@ impl TwitterClientTrait for TwitterClient {
         let consumer_key = &self.app_cred.consumer_key;
         let consumer_secret = &self.app_cred.consumer_secret;
 
-        let request_url = self
-            .server
-            .join(&format!("1.1/statuses/destroy/{}.json", tweet_id_str))?;
-        let query_params: Vec<QueryParam> = vec![];
+        let request_url = self.server.join("/1.1/favorites/destroy.json")?;
+        let query_params: Vec<QueryParam> = vec![QueryParam::new("id", tweet_id_str)];
 
         // https://rust-lang-nursery.github.io/rust-cookbook/encoding/strings.html#percent-encode-a-string
         let request_method = &String::from("POST");
 
-        let oauth_signature = build_oauth_signature(
-            oauth_token,
-            oauth_token_secret,
-            consumer_key,
-            consumer_secret,
-            request_url.clone(),
-            request_method,
-            query_params,
-        );
+        // 再送時にnonceが重複しないよう, リクエストごとに署名する
+        let signed_unlike_tweet_response = self.call_with_retry(Endpoint::Unlike, || {
+            let (oauth_nonce, oauth_timestamp) = generate_nonce_and_timestamp();
+            let oauth_signature = build_oauth_signature(
+                oauth_token,
+                oauth_token_secret,
+                consumer_key,
+                consumer_secret,
+                request_url.clone(),
+                request_method,
+                query_params.clone(),
+                &oauth_nonce,
+                &oauth_timestamp,
+            );
+
+            let mut signed_unlike_tweet_request =
+                HttpRequest::new(request_method, request_url.clone())
+                    .header("Authorization", &oauth_signature);
+
+            for each in &query_params {
+                signed_unlike_tweet_request =
+                    signed_unlike_tweet_request.query(&each.key, &each.value);
+            }
 
-        let signed_delete_tweet_response = self
-            .agent
-            .request_url(request_method.as_str(), &request_url)
-            .set("Authorization", &oauth_signature)
-            .call();
+            signed_unlike_tweet_request
+        });
 
-        match signed_delete_tweet_response {
+        match signed_unlike_tweet_response {
+            Ok(_) => Ok(UnlikeOutcome::Unliked),
+            Err(HttpError::Status(404, response)) => {
+                debug!(
+                    "The liked tweet {} is not found: {}",
+                    tweet_id_str,
+                    describe_error_body(404, &response.body)
+                );
+                Ok(UnlikeOutcome::AlreadyGone)
+            }
+            Err(e) => Err(describe_error("Failed to unlike.", e)),
+        }
+    }
+
+    /// Delete the scheduled tweet before it is posted
+    /// It requires the access to Twitter Ads API, see [`TwitterClient::fetch_scheduled()`]
+    /// * account_id: ads account id
+    /// * scheduled_tweet_id: target scheduled tweet id
+    fn delete_scheduled(&self, account_id: &str, scheduled_tweet_id: &str) -> Result<()> {
+        let request_url = ads_api_url(&format!(
+            "accounts/{}/scheduled_tweets/{}",
+            account_id, scheduled_tweet_id
+        ))?;
+        let delete_scheduled_response =
+            self.call_signed_with_retry(Endpoint::DeleteScheduled, "DELETE", &request_url, &[])?;
+        match delete_scheduled_response {
             Ok(_) => Ok(()),
-            Err(_) => Err(anyhow::anyhow!("Failed to delete.")),
+            Err(e) => Err(describe_ads_error(
+                "Failed to delete the scheduled tweet.",
+                e,
+            )),
+        }
+    }
+
+    /// Delete your tweet
+    /// The endpoint is chosen by [`TwitterClient::api_version()`]
+    /// * tweet_id_str: target tweet id
+    ///
+    /// 404 is not an error but [`DeleteOutcome::AlreadyGone`], because the tweet can be deleted by the previous run
+    /// or the eventual consistency of Twitter
+    fn delete_tweet(&self, tweet_id_str: &str) -> Result<DeleteOutcome> {
+        match self.api_version() {
+            ApiVersion::V1 => self.delete_tweet_v1(tweet_id_str),
+            ApiVersion::V2 => self.delete_tweet_v2(tweet_id_str),
+        }
+    }
+
+    /// Download the media file e.g. photo and video of the tweet
+    /// The media are served by CDN, so it is sent without the credentials
+    /// * url: URL of the media, see [`crate::twitter_object::Media::download_url()`]
+    fn download_media(&self, url: &str) -> Result<Vec<u8>> {
+        let request_url = Url::parse(url)?;
+        let request = HttpRequest::new("GET", request_url);
+        match self.retry_policy.retry(|| self.executor.download(&request)) {
+            Ok(body) => Ok(body),
+            Err(e) => Err(describe_error("Failed to download the media.", e)),
         }
     }
 
     /// Retrieve the tweets
-    /// It will get 100 tweets(MAX and fixed value)
+    /// It will get 100 tweets(MAX and fixed value) of the first page, see [`TwitterClient::fetch_timeline_page()`]
     /// * since: the first date of getting tweets e.g. 2022-01-01
     ///   It will be attached time and timezone after that date like 2022-01-01T00:00:00Z
     /// * until: the last date of getting tweets e.g. 2022-12-31
@@ -196,12 +576,35 @@ impl TwitterClientTrait for TwitterClient {
         since_arg: Option<String>,
         until_arg: Option<String>,
     ) -> Result<Vec<Tweet>> {
+        let page = self.fetch_timeline_page(since_arg, until_arg, None)?;
+        Ok(page.data)
+    }
+
+    /// Retrieve the page of the tweets
+    /// It will get 100 tweets from the page unless [`TwitterClient::with_page_size()`] is given
+    /// The next page waits for [`TwitterClient::with_page_interval()`] before the request
+    /// * since: the first date of getting tweets e.g. 2022-01-01
+    /// * until: the last date of getting tweets e.g. 2022-12-31
+    /// * pagination_token: `next_token` of the previous page, None for the first page
+    fn fetch_timeline_page(
+        &self,
+        since_arg: Option<String>,
+        until_arg: Option<String>,
+        pagination_token: Option<String>,
+    ) -> Result<ResponsePage<Tweet>> {
         let user_cred = match &self.user_cred {
             Some(cred) => cred,
             None => return Err(anyhow::anyhow!("Credential is not loaded.")),
         };
 
         info!("Pull the target tweets");
+        if (since_arg.is_some() || until_arg.is_some())
+            && (self.since_id.is_some() || self.until_id.is_some())
+        {
+            return Err(anyhow::anyhow!(
+                "The dates(since/until) and the ids(since_id/until_id) can't be used together"
+            ));
+        }
         let since = match since_arg {
             Some(since_arg) => {
                 let mut since_date = String::new();
@@ -226,81 +629,101 @@ impl TwitterClientTrait for TwitterClient {
         let consumer_key = &self.app_cred.consumer_key;
         let consumer_secret = &self.app_cred.consumer_secret;
 
+        let timeline_user_id = match &self.timeline_user_id {
+            Some(timeline_user_id) => timeline_user_id,
+            None => &user_cred.id,
+        };
         let request_url = self
             .server
-            .join(&format!("2/users/{}/tweets", &user_cred.id))?;
-        let mut query_params: Vec<QueryParam> = vec![
-            QueryParam::new("max_results", "100"),
-            QueryParam::new("tweet.fields", "created_at,public_metrics,attachments"),
-        ];
+            .join(&format!("2/users/{}/tweets", timeline_user_id))?;
+        let mut query_params: Vec<QueryParam> =
+            vec![QueryParam::new("max_results", &self.page_size.to_string())];
+        query_params.append(&mut self.tweet_query_params());
 
-        if since.is_some() && until.is_some() {
-            query_params.push(QueryParam::new(
-                "end_time",
-                until.as_ref().unwrap().as_str(),
-            ));
-            query_params.push(QueryParam::new(
-                "start_time",
-                since.as_ref().unwrap().as_str(),
-            ));
-        } else if since.is_some() {
-            query_params.push(QueryParam::new(
-                "start_time",
-                since.as_ref().unwrap().as_str(),
-            ));
-        } else if until.is_some() {
+        if let Some(until) = &until {
+            query_params.push(QueryParam::new("end_time", until.as_str()));
+        }
+        if let Some(since) = &since {
+            query_params.push(QueryParam::new("start_time", since.as_str()));
+        }
+        if let Some(since_id) = &self.since_id {
+            query_params.push(QueryParam::new("since_id", since_id.as_str()));
+        }
+        if let Some(until_id) = &self.until_id {
+            query_params.push(QueryParam::new("until_id", until_id.as_str()));
+        }
+        if let Some(pagination_token) = &pagination_token {
             query_params.push(QueryParam::new(
-                "end_time",
-                until.as_ref().unwrap().as_str(),
+                "pagination_token",
+                pagination_token.as_str(),
             ));
+            self.wait_page_interval();
         }
 
         let request_method = &String::from("GET");
 
-        let oauth_signature = build_oauth_signature(
-            oauth_token,
-            oauth_token_secret,
-            consumer_key,
-            consumer_secret,
-            request_url.clone(),
-            request_method,
-            query_params.clone(),
-        );
-
-        let mut signed_fetch_timeline_request = self
-            .agent
-            .request_url(request_method.as_str(), &request_url)
-            .set("Authorization", &oauth_signature);
         debug!("Request query key and value:");
-        for each in query_params {
+        for each in &query_params {
             debug!("\tkey:{}, value:{}", each.key, each.value);
-            signed_fetch_timeline_request =
-                signed_fetch_timeline_request.query(&each.key, &each.value);
         }
 
-        let signed_fetch_timeline_response = signed_fetch_timeline_request.call();
+        // 再送時にnonceが重複しないよう, リクエストごとに署名する
+        let signed_fetch_timeline_response = self.call_with_retry(Endpoint::Timeline, || {
+            let (oauth_nonce, oauth_timestamp) = generate_nonce_and_timestamp();
+            let oauth_signature = build_oauth_signature(
+                oauth_token,
+                oauth_token_secret,
+                consumer_key,
+                consumer_secret,
+                request_url.clone(),
+                request_method,
+                query_params.clone(),
+                &oauth_nonce,
+                &oauth_timestamp,
+            );
+
+            let mut signed_fetch_timeline_request =
+                HttpRequest::new(request_method, request_url.clone())
+                    .header("Authorization", &oauth_signature);
+            for each in &query_params {
+                signed_fetch_timeline_request =
+                    signed_fetch_timeline_request.query(&each.key, &each.value);
+            }
+
+            signed_fetch_timeline_request
+        });
 
         let signed_fetch_timeline_response = match signed_fetch_timeline_response {
             Ok(res) => res,
-            Err(e) => {
-                panic!("{}", e);
-            }
+            Err(e) => return Err(describe_error("Failed to fetch tweets.", e)),
         };
+        self.store_rate_limit(&signed_fetch_timeline_response);
         // load on the object for removing unnecessary prop
-        let response_object: ResponseObject<Vec<Tweet>> =
-            serde_json::from_reader(signed_fetch_timeline_response.into_reader())?;
-
-        debug!("Got: {} tweets", &response_object.data.len());
-        Ok(response_object.data)
+        let mut response_page: ResponsePage<Tweet> =
+            serde_json::from_str(&signed_fetch_timeline_response.body)?;
+        response_page.fill_referenced_authors();
+
+        debug!(
+            result_count = response_page.meta.result_count;
+            "Got: {} tweets (result_count: {})",
+            response_page.data.len(),
+            response_page.meta.result_count
+        );
+        Ok(response_page)
     }
 
     /// Retrieve the liked tweets
-    /// It will get xxx tweets(MAX and fixed value)
-    /// * since: the first date of getting tweets e.g. 2022-01-01
-    ///   It will be attached time and timezone after that date like 2022-01-01T00:00:00Z
-    /// * until: the last date of getting tweets e.g. 2022-12-31
-    ///   It will be attached time and timezone after that date like 2022-12-31T00:00:00Z
+    /// It will get 100 tweets(MAX and fixed value) from the first page, see [`TwitterClient::fetch_likes_page()`]
     fn fetch_likes(&self) -> Result<Vec<Tweet>> {
+        let page = self.fetch_likes_page(None)?;
+        Ok(page.data)
+    }
+
+    /// Retrieve the page of the liked tweets
+    /// It will get 100 tweets from the page unless [`TwitterClient::with_page_size()`] is given
+    /// The next page waits for [`TwitterClient::with_page_interval()`] before the request
+    /// * pagination_token: `next_token` of the previous page, None for the first page
+    fn fetch_likes_page(&self, pagination_token: Option<String>) -> Result<ResponsePage<Tweet>> {
         let user_cred = match &self.user_cred {
             Some(cred) => cred,
             None => return Err(anyhow::anyhow!("Credential is not loaded.")),
@@ -316,50 +739,274 @@ impl TwitterClientTrait for TwitterClient {
         let request_url = self
             .server
             .join(&format!("2/users/{}/liked_tweets", &user_cred.id))?;
-        let query_params: Vec<QueryParam> = vec![
-            QueryParam::new("max_results", "100"),
-            QueryParam::new("tweet.fields", "created_at,public_metrics,attachments"),
-        ];
+        // liked_tweetsは10件未満のページを受け付けない
+        let max_results = self.page_size.max(LIKES_MIN_PAGE_SIZE).to_string();
+        let mut query_params: Vec<QueryParam> = vec![QueryParam::new("max_results", &max_results)];
+        query_params.append(&mut self.tweet_query_params());
+        if let Some(pagination_token) = &pagination_token {
+            query_params.push(QueryParam::new(
+                "pagination_token",
+                pagination_token.as_str(),
+            ));
+            self.wait_page_interval();
+        }
 
         let request_method = &String::from("GET");
 
-        let oauth_signature = build_oauth_signature(
-            oauth_token,
-            oauth_token_secret,
-            consumer_key,
-            consumer_secret,
-            request_url.clone(),
-            request_method,
-            query_params.clone(),
-        );
-
-        let mut signed_fetch_timeline_request = self
-            .agent
-            .request_url(request_method.as_str(), &request_url)
-            .set("Authorization", &oauth_signature);
         debug!("Request query key and value:");
-        for each in query_params {
+        for each in &query_params {
             debug!("\tkey:{}, value:{}", each.key, each.value);
-            signed_fetch_timeline_request =
-                signed_fetch_timeline_request.query(&each.key, &each.value);
         }
 
-        let signed_fetch_timeline_response = signed_fetch_timeline_request.call();
+        // 再送時にnonceが重複しないよう, リクエストごとに署名する
+        let signed_fetch_timeline_response = self.call_with_retry(Endpoint::Likes, || {
+            let (oauth_nonce, oauth_timestamp) = generate_nonce_and_timestamp();
+            let oauth_signature = build_oauth_signature(
+                oauth_token,
+                oauth_token_secret,
+                consumer_key,
+                consumer_secret,
+                request_url.clone(),
+                request_method,
+                query_params.clone(),
+                &oauth_nonce,
+                &oauth_timestamp,
+            );
+
+            let mut signed_fetch_timeline_request =
+                HttpRequest::new(request_method, request_url.clone())
+                    .header("Authorization", &oauth_signature);
+            for each in &query_params {
+                signed_fetch_timeline_request =
+                    signed_fetch_timeline_request.query(&each.key, &each.value);
+            }
+
+            signed_fetch_timeline_request
+        });
 
         let signed_fetch_timeline_response = match signed_fetch_timeline_response {
             Ok(res) => res,
-            Err(e) => {
-                panic!("{}", e);
+            Err(e) => return Err(describe_error("Failed to fetch tweets.", e)),
+        };
+        self.store_rate_limit(&signed_fetch_timeline_response);
+        // load on the object for removing unnecessary prop
+        let response_page: ResponsePage<Tweet> =
+            serde_json::from_str(&signed_fetch_timeline_response.body)?;
+
+        debug!(
+            result_count = response_page.meta.result_count;
+            "Got: {} tweets (result_count: {})",
+            response_page.data.len(),
+            response_page.meta.result_count
+        );
+        Ok(response_page)
+    }
+
+    /// Retrieve the bookmarked tweets
+    /// It will get 100 tweets(MAX and fixed value)
+    /// It requires OAuth 2.0 user context, see [`TwitterClient::login_oauth2()`]
+    fn fetch_bookmarks(&self) -> Result<Vec<Tweet>> {
+        let user_cred = match &self.user_cred {
+            Some(cred) => cred,
+            None => return Err(anyhow::anyhow!("Credential is not loaded.")),
+        };
+        let access_token = self.oauth2_access_token()?;
+
+        info!("Pull the target tweets");
+
+        let request_url = self
+            .server
+            .join(&format!("2/users/{}/bookmarks", &user_cred.id))?;
+        let mut query_params: Vec<QueryParam> = vec![QueryParam::new("max_results", "100")];
+        query_params.append(&mut self.tweet_query_params());
+
+        debug!("Request query key and value:");
+        for each in &query_params {
+            debug!("\tkey:{}, value:{}", each.key, each.value);
+        }
+
+        let fetch_bookmarks_response = self.call_with_retry(Endpoint::Bookmarks, || {
+            let mut fetch_bookmarks_request = HttpRequest::new("GET", request_url.clone())
+                .header("Authorization", &format!("Bearer {}", access_token));
+            for each in &query_params {
+                fetch_bookmarks_request = fetch_bookmarks_request.query(&each.key, &each.value);
             }
+            fetch_bookmarks_request
+        });
+        let fetch_bookmarks_response = match fetch_bookmarks_response {
+            Ok(res) => res,
+            Err(e) => return Err(anyhow::anyhow!("Failed to fetch bookmarks: {}", e)),
         };
         // load on the object for removing unnecessary prop
         let response_object: ResponseObject<Vec<Tweet>> =
-            serde_json::from_reader(signed_fetch_timeline_response.into_reader())?;
+            serde_json::from_str(&fetch_bookmarks_response.body)?;
 
-        debug!("Got: {} tweets", &response_object.data.len());
+        let result_count = response_object.meta.as_ref().map(|meta| meta.result_count);
+        debug!(
+            "Got: {} tweets (result_count: {:?})",
+            response_object.data.len(),
+            result_count
+        );
         Ok(response_object.data)
     }
 
+    /// Get the page of your direct message events from the latest to the older
+    /// It requires OAuth 2.0 user context with `dm.read`, see [`TwitterClient::login_oauth2()`]
+    /// * pagination_token: `next_token` of the previous page, the first page if it is None
+    fn fetch_dm_events(&self, pagination_token: Option<String>) -> Result<ResponsePage<DmEvent>> {
+        let access_token = self.oauth2_access_token()?;
+
+        info!("Pull the target direct messages");
+
+        let request_url = self.server.join("2/dm_events")?;
+        let mut query_params: Vec<QueryParam> = vec![
+            QueryParam::new("max_results", "100"),
+            QueryParam::new("dm_event.fields", DM_EVENT_FIELDS),
+        ];
+        if let Some(pagination_token) = &pagination_token {
+            query_params.push(QueryParam::new(
+                "pagination_token",
+                pagination_token.as_str(),
+            ));
+        }
+
+        debug!("Request query key and value:");
+        for each in &query_params {
+            debug!("\tkey:{}, value:{}", each.key, each.value);
+        }
+
+        let fetch_dm_events_response = self.call_with_retry(Endpoint::DmEvents, || {
+            let mut fetch_dm_events_request = HttpRequest::new("GET", request_url.clone())
+                .header("Authorization", &format!("Bearer {}", access_token));
+            for each in &query_params {
+                fetch_dm_events_request = fetch_dm_events_request.query(&each.key, &each.value);
+            }
+            fetch_dm_events_request
+        });
+        let fetch_dm_events_response = match fetch_dm_events_response {
+            Ok(res) => res,
+            Err(e) => return Err(describe_error("Failed to fetch direct messages.", e)),
+        };
+        let response_page: ResponsePage<DmEvent> =
+            serde_json::from_str(&fetch_dm_events_response.body)?;
+
+        debug!("Got: {} direct message events", &response_page.data.len());
+        Ok(response_page)
+    }
+
+    /// Retrieve the scheduled tweets of the ads account
+    /// It requires the access to Twitter Ads API, the app must be approved for it and
+    /// the user must be the owner(or a user) of the ads account
+    /// * account_id: ads account id, it is shown on ads.twitter.com
+    /// * cursor: `next_cursor` of the previous page, the first page if it is None
+    fn fetch_scheduled(&self, account_id: &str, cursor: Option<String>) -> Result<ScheduledPage> {
+        info!("Pull the scheduled tweets");
+
+        let request_url = ads_api_url(&format!("accounts/{}/scheduled_tweets", account_id))?;
+        let mut query_params: Vec<QueryParam> =
+            vec![QueryParam::new("count", SCHEDULED_TWEETS_PER_PAGE)];
+        if let Some(cursor) = &cursor {
+            query_params.push(QueryParam::new("cursor", cursor.as_str()));
+        }
+
+        let fetch_scheduled_response =
+            self.call_signed_with_retry(Endpoint::Scheduled, "GET", &request_url, &query_params)?;
+        let fetch_scheduled_response = match fetch_scheduled_response {
+            Ok(res) => res,
+            Err(e) => {
+                return Err(describe_ads_error(
+                    "Failed to fetch the scheduled tweets.",
+                    e,
+                ))
+            }
+        };
+        let scheduled_page: ScheduledPage = serde_json::from_str(&fetch_scheduled_response.body)?;
+
+        debug!("Got: {} scheduled tweets", &scheduled_page.data.len());
+        Ok(scheduled_page)
+    }
+
+    /// Look up the tweets by the ids, the deleted ones are in `errors` of the response
+    /// * ids: up to [`TWEETS_PER_LOOKUP`] tweet ids
+    fn fetch_tweets_by_ids(&self, ids: &[String]) -> Result<ResponseLookup<Tweet>> {
+        if ids.len() > TWEETS_PER_LOOKUP {
+            return Err(anyhow::anyhow!(
+                "Too many ids to look up at once: {} > {}",
+                ids.len(),
+                TWEETS_PER_LOOKUP
+            ));
+        }
+        let request_url = self.server.join("2/tweets")?;
+        let mut query_params: Vec<QueryParam> = vec![QueryParam::new("ids", &ids.join(","))];
+        query_params.append(&mut self.tweet_query_params());
+
+        let lookup_response = self.call_signed_with_retry(
+            Endpoint::LookupTweets,
+            "GET",
+            &request_url,
+            &query_params,
+        )?;
+        let lookup_response = match lookup_response {
+            Ok(res) => res,
+            Err(e) => return Err(describe_error("Failed to look up the tweets.", e)),
+        };
+        let lookup: ResponseLookup<Tweet> = serde_json::from_str(&lookup_response.body)?;
+
+        debug!(
+            "Got: {} tweets and {} errors",
+            lookup.data.len(),
+            lookup.errors.len()
+        );
+        Ok(lookup)
+    }
+
+    /// Get the logged-in user, it is also the validation of the stored user credential
+    fn get_me(&self) -> Result<User> {
+        let user_cred = match &self.user_cred {
+            Some(cred) => cred,
+            None => return Err(anyhow::anyhow!("Credential is not loaded.")),
+        };
+
+        let oauth_token = &user_cred.oauth_token;
+        let oauth_token_secret = &user_cred.oauth_token_secret;
+        let consumer_key = &self.app_cred.consumer_key;
+        let consumer_secret = &self.app_cred.consumer_secret;
+
+        let request_url = self.server.join("2/users/me")?;
+        let query_params: Vec<QueryParam> = vec![QueryParam::new("user.fields", USER_FIELDS)];
+        let request_method = &String::from("GET");
+
+        // 再送時にnonceが重複しないよう, リクエストごとに署名する
+        let signed_get_me_response = self.call_with_retry(Endpoint::Me, || {
+            let (oauth_nonce, oauth_timestamp) = generate_nonce_and_timestamp();
+            let oauth_signature = build_oauth_signature(
+                oauth_token,
+                oauth_token_secret,
+                consumer_key,
+                consumer_secret,
+                request_url.clone(),
+                request_method,
+                query_params.clone(),
+                &oauth_nonce,
+                &oauth_timestamp,
+            );
+
+            let mut signed_get_me_request = HttpRequest::new(request_method, request_url.clone())
+                .header("Authorization", &oauth_signature);
+            for each in &query_params {
+                signed_get_me_request = signed_get_me_request.query(&each.key, &each.value);
+            }
+            signed_get_me_request
+        });
+
+        let signed_get_me_response = match signed_get_me_response {
+            Ok(res) => res,
+            Err(e) => return Err(describe_error("Failed to get the logged-in user.", e)),
+        };
+        let user_object: ResponseObject<User> = serde_json::from_str(&signed_get_me_response.body)?;
+        Ok(user_object.data)
+    }
+
     /// * user_cred: app defined user credential struct
     ///   It is expected to come from [`TwitterClient::login()`]
     fn init_user_cred(mut self, user_cred: TwitterAppUserCredential) -> Result<TwitterClient> {
@@ -372,48 +1019,63 @@ impl TwitterClientTrait for TwitterClient {
     /// ref: <https://developer.twitter.com/ja/docs/basics/authentication/overview/pin-based-oauth>
     fn login(&self) -> Result<TwitterAppUserCredential> {
         // User input
-        info!("Please input your Twitter username:");
-        let mut username_input = String::new();
-        std::io::stdin().read_line(&mut username_input)?;
-        let username = username_input.trim().to_string();
+        let username = read_login_input(
+            &mut std::io::stdin().lock(),
+            "Please input your Twitter username:",
+            "username",
+        )?;
 
-        let liveness_request = self
-            .server
-            .join(&format!("2/users/by/username/{}", username))?;
-        let liveness_response = self
-            .agent
-            .request_url("GET", &liveness_request)
-            .set(
-                "Authorization",
-                &format!("Bearer {}", self.app_cred.api_key),
-            )
-            .call()?;
+        let request_token = self.request_login(&username)?;
+        self.show_authorize_url(&request_token.authorize_url);
 
-        let user_object: ResponseObject<User> =
-            serde_json::from_reader(liveness_response.into_reader())?;
+        // user input again, in here just PIN code
+        let pin = read_login_input(
+            &mut std::io::stdin().lock(),
+            "After authorize app, please input PIN number on the screen for complete the authorization process:",
+            "PIN",
+        )?;
 
-        let user_id = user_object.data.id;
+        self.complete_login(&request_token, &pin)
+    }
 
-        info!("Your username and user id is confirmed.");
+    /// The first phase of [`TwitterClient::login()`], it gets the request token and the URL to authorize the app
+    /// * username: your username
+    fn request_login(&self, username: &str) -> Result<TwitterRequestToken> {
+        let username = username.to_string();
+        let user_id = match self.lookup_user(&username) {
+            Ok(user) => user.id,
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "Login was failed at the liveness check. {}",
+                    e
+                ))
+            }
+        };
 
-        let mut work_path = env::temp_dir();
-        work_path.push("dta4hana.work.json");
+        info!("Your username and user id is confirmed.");
 
         // "request token" request
         let request_token_request = self.server.join(&format!(
             "oauth/request_token?oauth_consumer_key={}&oauth_callback=oob",
             self.app_cred.consumer_key
         ))?;
-        let token_request_response = self
-            .agent
-            .request_url("POST", &request_token_request)
-            .set(
-                "Authorization",
-                &format!("Bearer {}", self.app_cred.api_key),
-            )
-            .call()?;
-
-        let result = token_request_response.into_string()?;
+        let token_request_response =
+            match call_with_retry(self.executor.as_ref(), &self.retry_policy, || {
+                HttpRequest::new("POST", request_token_request.clone()).header(
+                    "Authorization",
+                    &format!("Bearer {}", self.app_cred.api_key),
+                )
+            }) {
+                Ok(res) => res,
+                Err(e) => {
+                    return Err(describe_error(
+                        "Login was failed at getting the request token.",
+                        e,
+                    ))
+                }
+            };
+
+        let result = token_request_response.body;
         let result_map: Vec<&str> = result.split('&').collect();
 
         // oauth_callback_confirmed, oauth_token, oauth_token_secret
@@ -432,28 +1094,42 @@ impl TwitterClientTrait for TwitterClient {
             .server
             .join(&format!("oauth/authorize?oauth_token={}", req_oauth_token))?;
 
-        info!(
-            "Please open this URL in your browser: {}",
-            authorize_request.to_string()
-        );
-
-        // user input again, in here just PIN code
-        info!("After authorize app, please input PIN number on the screen for complete the authorization process:");
-        let mut s = String::new();
-        std::io::stdin().read_line(&mut s)?;
+        Ok(TwitterRequestToken {
+            username,
+            id: user_id,
+            oauth_token: req_oauth_token,
+            authorize_url: authorize_request.to_string(),
+        })
+    }
 
+    /// The second phase of [`TwitterClient::login()`], it exchanges the request token and the PIN for the user credential
+    /// * request_token: request token returned by [`TwitterClient::request_login()`]
+    /// * pin: PIN shown after authorizing the app
+    fn complete_login(
+        &self,
+        request_token: &TwitterRequestToken,
+        pin: &str,
+    ) -> Result<TwitterAppUserCredential> {
         // completed authentication
         let access_token_request = self.server.join(&format!(
             "oauth/access_token?oauth_token={}&oauth_verifier={}",
-            req_oauth_token,
-            s.trim()
+            request_token.oauth_token, pin
         ))?;
-        let access_token_response = self
-            .agent
-            .request_url("POST", &access_token_request)
-            .call()?;
-
-        let result = access_token_response.into_string()?;
+        // 通信エラーだけを再送する, 使用済みのPINは401でそのまま返る
+        let access_token_response =
+            match call_with_retry(self.executor.as_ref(), &self.retry_policy, || {
+                HttpRequest::new("POST", access_token_request.clone())
+            }) {
+                Ok(res) => res,
+                Err(e) => {
+                    return Err(describe_error(
+                        "Login was failed at exchanging the PIN for the access token.",
+                        e,
+                    ))
+                }
+            };
+
+        let result = access_token_response.body;
         let result_map: Vec<&str> = result.split('&').collect();
         // oauth_token, oauth_token_secret, user_id, screen_name
         let mut access_token_keys: HashMap<&str, &str> = HashMap::new();
@@ -472,52 +1148,784 @@ impl TwitterClientTrait for TwitterClient {
             None => return Err(anyhow::anyhow!("No token secret is found")),
         };
         let user_cred = TwitterAppUserCredential {
-            username,
-            id: user_id,
+            username: request_token.username.clone(),
+            id: request_token.id.clone(),
             oauth_token,
             oauth_token_secret,
+            oauth2: None,
         };
         Ok(user_cred)
     }
-}
 
-/// Build OAuth(1.0a) Signature value
-/// Encode, sort, join and such required process will be handled
-/// Nonce and timestamp will be generated and this will return the value of authorization header
-///
-/// You can use returned value as `Authorization` value in the header
-fn build_oauth_signature(
-    oauth_token: &String,
-    oauth_token_secret: &String,
-    consumer_key: &String,
-    consumer_secret: &String,
-    target_endpoint: Url,
-    request_method: &String,
-    query_params: Vec<QueryParam>,
-) -> String {
-    // Prepare required params other than arguments
-    // TODO: Remove unwrap
-    let oauth_signature_method = "HMAC-SHA1";
-    let oauth_version = "1.0";
-    let oauth_nonce = &Uuid::new_v4().to_string();
-    let oauth_timestamp = &SystemTime::now()
+    /// Login with OAuth 2.0 and return the user access token
+    /// It is based on Authorization Code Flow with PKCE, and the app is treated as public client
+    /// The user authorizes the app on the browser and copies the redirected URL back
+    /// ref: <https://developer.twitter.com/en/docs/authentication/oauth-2-0/authorization-code>
+    /// * client_id: OAuth 2.0 Client ID of the app
+    fn login_oauth2(&self, client_id: &str) -> Result<TwitterOAuth2Credential> {
+        // code_challenge_method=plain, verifier needs 43-128 chars
+        let code_verifier = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let state = Uuid::new_v4().simple().to_string();
+
+        let authorize_request = Url::parse_with_params(
+            OAUTH2_AUTHORIZE_URL,
+            &[
+                ("response_type", "code"),
+                ("client_id", client_id),
+                ("redirect_uri", OAUTH2_REDIRECT_URI),
+                ("scope", self.oauth2_scopes.as_str()),
+                ("state", state.as_str()),
+                ("code_challenge", code_verifier.as_str()),
+                ("code_challenge_method", "plain"),
+            ],
+        )?;
+
+        self.show_authorize_url(authorize_request.as_str());
+        let redirected_url = read_login_input(
+            &mut std::io::stdin().lock(),
+            &format!("After authorize app, please input the redirected URL(it starts with {}) for complete the authorization process:", OAUTH2_REDIRECT_URI),
+            "redirected URL",
+        )?;
+        let code = parse_oauth2_code(&redirected_url, &state)?;
+
+        let token_request = self.server.join("2/oauth2/token")?;
+        let token_response = self.call(HttpRequest::new("POST", token_request).form(&[
+            ("code", code.as_str()),
+            ("grant_type", "authorization_code"),
+            ("client_id", client_id),
+            ("redirect_uri", OAUTH2_REDIRECT_URI),
+            ("code_verifier", code_verifier.as_str()),
+        ]))?;
+
+        let oauth2_cred: TwitterOAuth2Credential = serde_json::from_str(&token_response.body)?;
+        Ok(oauth2_cred)
+    }
+
+    /// Rate limit of the last fetch request e.g. [`TwitterClient::fetch_timeline()`]
+    /// It is None if no fetch request was sent yet or the response didn't have the headers
+    /// Look up the user by the username, it is also the liveness check of the Bearer Token
+    /// * username: username without `@`
+    fn lookup_user(&self, username: &str) -> Result<User> {
+        let liveness_request = self
+            .server
+            .join(&format!("2/users/by/username/{}", username))?;
+        let liveness_response = match self.call_with_retry(Endpoint::LookupUser, || {
+            HttpRequest::new("GET", liveness_request.clone())
+                .header(
+                    "Authorization",
+                    &format!("Bearer {}", self.app_cred.api_key),
+                )
+                .query("user.fields", USER_FIELDS)
+        }) {
+            Ok(res) => res,
+            Err(e) => return Err(describe_error("Failed to look up the user.", e)),
+        };
+
+        let user_object: ResponseObject<User> = serde_json::from_str(&liveness_response.body)?;
+        Ok(user_object.data)
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+}
+
+impl TwitterClient {
+    /// Get OAuth 2.0 user access token
+    /// It returns an error if the user didn't login with OAuth 2.0 yet
+    fn oauth2_access_token(&self) -> Result<&str> {
+        match &self.user_cred {
+            Some(TwitterAppUserCredential {
+                oauth2: Some(oauth2),
+                ..
+            }) => Ok(&oauth2.access_token),
+            _ => Err(anyhow::anyhow!(
+                "OAuth 2.0 credential is not loaded. Please login with `login --oauth2` first."
+            )),
+        }
+    }
+
+    /// Delete your tweet with v1.1 `statuses/destroy` and OAuth 1.0a
+    /// * tweet_id_str: target tweet id
+    fn delete_tweet_v1(&self, tweet_id_str: &str) -> Result<DeleteOutcome> {
+        let user_cred = match &self.user_cred {
+            Some(cred) => cred,
+            None => return Err(anyhow::anyhow!("Credential is not loaded.")),
+        };
+
+        let oauth_token = &user_cred.oauth_token;
+        let oauth_token_secret = &user_cred.oauth_token_secret;
+        let consumer_key = &self.app_cred.consumer_key;
+        let consumer_secret = &self.app_cred.consumer_secret;
+
+        let request_url = self
+            .server
+            .join(&format!("1.1/statuses/destroy/{}.json", tweet_id_str))?;
+        let query_params: Vec<QueryParam> = vec![];
+
+        // https://rust-lang-nursery.github.io/rust-cookbook/encoding/strings.html#percent-encode-a-string
+        let request_method = &String::from("POST");
+
+        // 再送時にnonceが重複しないよう, リクエストごとに署名する
+        let signed_delete_tweet_response = self.call_with_retry(Endpoint::DeleteTweet, || {
+            let (oauth_nonce, oauth_timestamp) = generate_nonce_and_timestamp();
+            let oauth_signature = build_oauth_signature(
+                oauth_token,
+                oauth_token_secret,
+                consumer_key,
+                consumer_secret,
+                request_url.clone(),
+                request_method,
+                query_params.clone(),
+                &oauth_nonce,
+                &oauth_timestamp,
+            );
+
+            HttpRequest::new(request_method, request_url.clone())
+                .header("Authorization", &oauth_signature)
+        });
+
+        match signed_delete_tweet_response {
+            Ok(_) => Ok(DeleteOutcome::Deleted),
+            Err(HttpError::Status(404, response)) => Ok(already_gone(tweet_id_str, &response)),
+            Err(e) => Err(describe_error("Failed to delete.", e)),
+        }
+    }
+
+    /// Delete your tweet with v2 `DELETE /2/tweets/:id` and OAuth 2.0 user context
+    /// `deleted: false` in the response is also a failure
+    /// * tweet_id_str: target tweet id
+    fn delete_tweet_v2(&self, tweet_id_str: &str) -> Result<DeleteOutcome> {
+        let access_token = self.oauth2_access_token()?;
+
+        let request_url = self.server.join(&format!("2/tweets/{}", tweet_id_str))?;
+
+        let delete_tweet_response = self.call_with_retry(Endpoint::DeleteTweet, || {
+            HttpRequest::new("DELETE", request_url.clone())
+                .header("Authorization", &format!("Bearer {}", access_token))
+        });
+        let delete_tweet_response = match delete_tweet_response {
+            Ok(res) => res,
+            Err(HttpError::Status(404, response)) => {
+                return Ok(already_gone(tweet_id_str, &response))
+            }
+            Err(e) => return Err(describe_error("Failed to delete.", e)),
+        };
+
+        let deleted: ResponseObject<Deleted> = serde_json::from_str(&delete_tweet_response.body)?;
+        if deleted.data.deleted {
+            Ok(DeleteOutcome::Deleted)
+        } else {
+            Err(anyhow::anyhow!(
+                "Failed to delete. The tweet {} was not deleted: {}",
+                tweet_id_str,
+                delete_tweet_response.body
+            ))
+        }
+    }
+
+    /// Show the authorize URL of the login, it is opened in the browser if it is requested and possible
+    /// The URL is always printed, so the user can open it by hand if the browser didn't come up
+    /// * authorize_url: URL to authorize the app
+    fn show_authorize_url(&self, authorize_url: &str) {
+        if self.open_browser {
+            if !is_browser_available(|name| {
+                std::env::var_os(name).is_some_and(|value| !value.is_empty())
+            }) {
+                info!("No browser is available in this session e.g. SSH, please open the URL by yourself.");
+            } else if let Err(e) = webbrowser::open(authorize_url) {
+                warn!("Failed to open the browser: {}", e);
+            } else {
+                info!("Opened this URL in your browser: {}", authorize_url);
+                return;
+            }
+        }
+        info!("Please open this URL in your browser: {}", authorize_url);
+    }
+
+    /// Keep the rate limit of the response for [`TwitterClient::rate_limit()`]
+    fn store_rate_limit(&self, response: &HttpResponse) {
+        let rate_limit = RateLimit::from_response(response);
+        debug!("Rate limit: {:?}", rate_limit);
+        *self.rate_limit.lock().unwrap() = rate_limit;
+    }
+
+    /// Send the request and retry it on the network errors, see [`call_with_retry()`]
+    /// Each attempt waits for the rate limiter of the endpoint
+    /// * endpoint: endpoint of the request for the rate limiter
+    /// * build_request: build the request, it is called on each attempt
+    fn call_with_retry(
+        &self,
+        endpoint: Endpoint,
+        build_request: impl Fn() -> HttpRequest,
+    ) -> Result<HttpResponse, HttpError> {
+        call_with_retry(self.executor.as_ref(), &self.retry_policy, || {
+            self.rate_limiter.acquire(endpoint);
+            build_request()
+        })
+    }
+
+    /// Sign the request with OAuth 1.0a user context and send it, see [`call_with_retry()`]
+    /// The outer error is the missing credential, and the inner one is the error of the request
+    /// * endpoint: endpoint of the request for the rate limiter
+    /// * request_method: e.g. GET, DELETE
+    /// * request_url: endpoint without the query
+    /// * query_params: they are signed and sent as the query
+    fn call_signed_with_retry(
+        &self,
+        endpoint: Endpoint,
+        request_method: &str,
+        request_url: &Url,
+        query_params: &[QueryParam],
+    ) -> Result<Result<HttpResponse, HttpError>> {
+        let user_cred = match &self.user_cred {
+            Some(cred) => cred,
+            None => return Err(anyhow::anyhow!("Credential is not loaded.")),
+        };
+        let request_method = &request_method.to_string();
+
+        // 再送時にnonceが重複しないよう, リクエストごとに署名する
+        Ok(self.call_with_retry(endpoint, || {
+            let (oauth_nonce, oauth_timestamp) = generate_nonce_and_timestamp();
+            let oauth_signature = build_oauth_signature(
+                &user_cred.oauth_token,
+                &user_cred.oauth_token_secret,
+                &self.app_cred.consumer_key,
+                &self.app_cred.consumer_secret,
+                request_url.clone(),
+                request_method,
+                query_params.to_vec(),
+                &oauth_nonce,
+                &oauth_timestamp,
+            );
+
+            let mut signed_request = HttpRequest::new(request_method, request_url.clone())
+                .header("Authorization", &oauth_signature);
+            for each in query_params {
+                signed_request = signed_request.query(&each.key, &each.value);
+            }
+            signed_request
+        }))
+    }
+
+    /// Send the request without retry e.g. the OAuth 2.0 token exchange, whose code can't be sent twice
+    fn call(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+        self.executor.call(&request)
+    }
+
+    /// Base URL of the APIs
+    pub fn server(&self) -> &Url {
+        &self.server
+    }
+
+    /// Username of the logged-in user, None if the user credential is not loaded
+    pub fn username(&self) -> Option<&str> {
+        self.user_cred.as_ref().map(|cred| cred.username.as_str())
+    }
+
+    /// Id of the logged-in user, None if the user credential is not loaded
+    pub fn user_id(&self) -> Option<&str> {
+        self.user_cred.as_ref().map(|cred| cred.id.as_str())
+    }
+
+    /// Pinned tweet of the logged-in user, None if it is not pinned or not fetched yet
+    pub fn pinned_tweet_id(&self) -> Option<&str> {
+        self.pinned_tweet_id.as_deref()
+    }
+
+    /// API version of the deletion
+    /// It is V1 unless it is given by [`TwitterClient::with_api_version()`].
+    /// V2 is not the default even with the OAuth 2.0 credential, because its access token is not refreshed yet
+    pub fn api_version(&self) -> ApiVersion {
+        self.api_version.unwrap_or(ApiVersion::V1)
+    }
+
+    /// Open the authorize URL in the default browser during the login instead of only printing it
+    /// It falls back to printing the URL in the headless sessions e.g. SSH without X11 forwarding
+    /// * open_browser: whether to open the browser
+    pub fn with_open_browser(mut self, open_browser: bool) -> Self {
+        self.open_browser = open_browser;
+        self
+    }
+
+    /// Request these scopes by `login --oauth2` instead of all the scopes of the actions
+    /// * scopes: separated by space or comma e.g. `tweet.read,tweet.write,users.read`
+    pub fn with_oauth2_scopes(mut self, scopes: &str) -> Self {
+        let mut normalized: Vec<&str> = vec![];
+        for scope in scopes.split(|c: char| c == ',' || c.is_whitespace()) {
+            if !scope.is_empty() && !normalized.contains(&scope) {
+                normalized.push(scope);
+            }
+        }
+        self.oauth2_scopes = normalized.join(" ");
+        self
+    }
+
+    /// Check the scopes granted to the stored OAuth 2.0 credential before running the action
+    /// It is for failing early instead of 403 in the middle of the action
+    /// The credential without the recorded scopes is not checked, it was stored by the older version
+    /// * scopes: scopes which the action needs e.g. [`UNBOOKMARK_SCOPES`]
+    ///
+    /// It returns an error if the OAuth 2.0 credential is not loaded or any of the scopes is not granted
+    pub fn require_oauth2_scopes(&self, scopes: &[&str]) -> Result<()> {
+        let oauth2 = match &self.user_cred {
+            Some(TwitterAppUserCredential {
+                oauth2: Some(oauth2),
+                ..
+            }) => oauth2,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "OAuth 2.0 credential is not loaded. Please login with `login --oauth2` first."
+                ))
+            }
+        };
+        let granted: Vec<&str> = match &oauth2.scope {
+            Some(scope) => scope.split_whitespace().collect(),
+            None => {
+                debug!("Granted scopes are not recorded, skip the scope check.");
+                return Ok(());
+            }
+        };
+        let missing: Vec<&str> = scopes
+            .iter()
+            .filter(|scope| !granted.contains(scope))
+            .copied()
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        // 既存のスコープを失わないよう, 再ログインでは両方を要求する
+        let relogin: Vec<&str> = granted.iter().chain(missing.iter()).copied().collect();
+        Err(anyhow::anyhow!(
+            "This action needs the {} scope, re-login with `login --oauth2 --scopes \"{}\"`.",
+            missing.join(" "),
+            relogin.join(" ")
+        ))
+    }
+
+    /// Delete the tweets with this API version instead of the default, see [`TwitterClient::api_version()`]
+    /// * api_version: V2 requires `login --oauth2`
+    pub fn with_api_version(mut self, api_version: ApiVersion) -> Self {
+        self.api_version.replace(api_version);
+        self
+    }
+
+    /// Remember the pinned tweet of the logged-in user, see [`TwitterClientTrait::get_me()`]
+    /// * pinned_tweet_id: `pinned_tweet_id` of the logged-in user
+    pub fn with_pinned_tweet(mut self, pinned_tweet_id: Option<String>) -> Self {
+        self.pinned_tweet_id = pinned_tweet_id;
+        self
+    }
+
+    /// Fetch the timeline of another user instead of the logged-in user
+    /// It is only for the read-only actions, the destroy requests are always for the logged-in user
+    /// * user_id: user id of the timeline, see [`TwitterClientTrait::lookup_user()`]
+    pub fn with_timeline_user(mut self, user_id: &str) -> Self {
+        self.timeline_user_id.replace(user_id.to_string());
+        self
+    }
+
+    /// Request these `tweet.fields` instead of the default, e.g. `lang,source`
+    /// The fields required by the actions(e.g. `created_at`) are always added, and the unknown ones are warned
+    /// * fields: comma separated `tweet.fields`
+    pub fn with_tweet_fields(mut self, fields: &str) -> Self {
+        let mut fields = normalize_csv(fields, &KNOWN_TWEET_FIELDS, "tweet.fields");
+        for required in REQUIRED_TWEET_FIELDS.iter().rev() {
+            if !fields.iter().any(|field| field == required) {
+                fields.insert(0, required.to_string());
+            }
+        }
+        self.tweet_fields = fields.join(",");
+        self
+    }
+
+    /// Add these `tweet.fields` to the current ones if they are not requested yet
+    /// They are e.g. the fields read by the filters of the deletion, see [`crate::dta_app::DeleteOptions::required_tweet_fields()`]
+    /// * fields: `tweet.fields` to be requested
+    pub fn with_required_tweet_fields(mut self, fields: &[&str]) -> Self {
+        for field in fields {
+            if !self.tweet_fields.split(',').any(|each| each == *field) {
+                self.tweet_fields = format!("{},{}", self.tweet_fields, field);
+            }
+        }
+        self
+    }
+
+    /// `tweet.fields` of the requests which return the tweets
+    pub fn tweet_fields(&self) -> &str {
+        &self.tweet_fields
+    }
+
+    /// Fetch the timeline and the likes by this number of tweets per page instead of [`MAX_PAGE_SIZE`]
+    /// The smaller pages are gentler to the rate limits and e.g. for testing the pagination
+    /// * page_size: `max_results` between [`MIN_PAGE_SIZE`] and [`MAX_PAGE_SIZE`], the likes are at least 10
+    ///
+    /// It returns an error if the page size is out of the range
+    pub fn with_page_size(mut self, page_size: u8) -> Result<Self> {
+        if !(MIN_PAGE_SIZE..=MAX_PAGE_SIZE).contains(&page_size) {
+            return Err(anyhow::anyhow!(
+                "Page size must be between {} and {}: {}",
+                MIN_PAGE_SIZE,
+                MAX_PAGE_SIZE,
+                page_size
+            ));
+        }
+        self.page_size = page_size;
+        Ok(self)
+    }
+
+    /// Sleep between the pages of the timeline and the likes, the first page is not delayed
+    /// It paces the reads separately from the interval of the deletion
+    /// * page_interval: no wait if it is zero
+    pub fn with_page_interval(mut self, page_interval: Duration) -> Self {
+        self.page_interval = page_interval;
+        self
+    }
+
+    /// Wait for [`TwitterClient::with_page_interval()`] before the next page
+    fn wait_page_interval(&self) {
+        self.wait_page_interval_with_sleep(std::thread::sleep)
+    }
+
+    /// [`TwitterClient::wait_page_interval()`] with the given sleep, the tests can replace the clock
+    /// * sleep: wait for the page interval
+    fn wait_page_interval_with_sleep(&self, mut sleep: impl FnMut(Duration)) {
+        if !self.page_interval.is_zero() {
+            debug!(
+                "Waiting for {}ms before the next page.",
+                self.page_interval.as_millis()
+            );
+            sleep(self.page_interval);
+        }
+    }
+
+    /// Bound the timeline by the tweet ids instead of the dates
+    /// Both ids are inclusive, they are converted to the exclusive `since_id` and `until_id` of the API
+    /// * since_id: the oldest tweet id of the timeline, no limit if it is None
+    /// * until_id: the newest tweet id of the timeline, no limit if it is None
+    ///
+    /// It returns an error if the ids are not numeric or since_id is newer than until_id
+    pub fn with_id_range(mut self, since_id: Option<&str>, until_id: Option<&str>) -> Result<Self> {
+        let since_id = since_id
+            .map(|id| parse_tweet_id(id, "since_id"))
+            .transpose()?;
+        let until_id = until_id
+            .map(|id| parse_tweet_id(id, "until_id"))
+            .transpose()?;
+        if let (Some(since_id), Some(until_id)) = (since_id, until_id) {
+            if since_id > until_id {
+                return Err(anyhow::anyhow!(
+                    "since_id({}) must be the same or older than until_id({})",
+                    since_id,
+                    until_id
+                ));
+            }
+        }
+        // 0より前のIDはないため, since_idは指定しない
+        self.since_id = since_id
+            .and_then(|id| id.checked_sub(1))
+            .filter(|id| *id > 0)
+            .map(|id| id.to_string());
+        self.until_id = until_id
+            .and_then(|id| id.checked_add(1))
+            .map(|id| id.to_string());
+        Ok(self)
+    }
+
+    /// Request these `expansions` additionally, e.g. `attachments.media_keys`
+    /// The expanded objects are in `includes` of the response, see [`ResponsePage`]
+    /// * expansions: comma separated `expansions`, the unknown ones are warned
+    pub fn with_expansions(mut self, expansions: &str) -> Self {
+        let expansions = normalize_csv(expansions, &KNOWN_EXPANSIONS, "expansions");
+        self.expansions = if expansions.is_empty() {
+            None
+        } else {
+            Some(expansions.join(","))
+        };
+        self
+    }
+
+    /// Request `attachments.media_keys` in addition to the current `expansions`
+    /// It is required to download the media of the tweets, see [`TwitterClientTrait::download_media()`]
+    pub fn with_media_expansion(self) -> Self {
+        let expansions = match &self.expansions {
+            Some(expansions) => format!("{},attachments.media_keys", expansions),
+            None => "attachments.media_keys".to_string(),
+        };
+        self.with_expansions(&expansions)
+    }
+
+    /// Request `referenced_tweets.id` and `author_id` in addition to the current `expansions` and `tweet.fields`
+    /// The authors of the referenced tweets are filled in the timeline, see [`ResponsePage::fill_referenced_authors()`]
+    pub fn with_referenced_authors(self) -> Self {
+        let expansions = match &self.expansions {
+            Some(expansions) => format!("{},referenced_tweets.id", expansions),
+            None => "referenced_tweets.id".to_string(),
+        };
+        self.with_required_tweet_fields(&["author_id"])
+            .with_expansions(&expansions)
+    }
+
+    /// Query parameters of the endpoints which return the tweets
+    fn tweet_query_params(&self) -> Vec<QueryParam> {
+        let mut query_params = vec![QueryParam::new("tweet.fields", &self.tweet_fields)];
+        if let Some(expansions) = &self.expansions {
+            query_params.push(QueryParam::new("expansions", expansions));
+            if expansions.contains("attachments.media_keys") {
+                query_params.push(QueryParam::new("media.fields", MEDIA_FIELDS));
+            }
+        }
+        query_params
+    }
+
+    /// Replace the HTTP layer, it is mainly for the tests with canned responses
+    /// * executor: it sends all the requests of this client
+    pub fn with_executor(mut self, executor: impl HttpExecutor + 'static) -> Self {
+        self.executor = Box::new(executor);
+        self
+    }
+
+    /// Share the rate limiter with another client, e.g. the clients of the same user
+    /// * rate_limiter: it limits all the requests of this client except the login flow
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+}
+
+/// Whether a browser can be launched in this session
+/// Windows and macOS have a browser unless it is an SSH session,
+/// the other platforms need X11/Wayland or `$BROWSER`
+/// * is_set: whether the environment variable is set and not empty
+fn is_browser_available(is_set: impl Fn(&str) -> bool) -> bool {
+    let display = is_set("DISPLAY") || is_set("WAYLAND_DISPLAY");
+    if is_set("SSH_CONNECTION") || is_set("SSH_TTY") {
+        // X11 forwardingがあればブラウザを開ける
+        return display;
+    }
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        return true;
+    }
+    display || is_set("BROWSER")
+}
+
+/// [`DeleteOutcome::AlreadyGone`] with the debug log of the 404 response
+/// * tweet_id_str: target tweet id
+/// * response: 404 response of the deletion
+fn already_gone(tweet_id_str: &str, response: &HttpResponse) -> DeleteOutcome {
+    debug!(
+        "The tweet {} is not found: {}",
+        tweet_id_str,
+        describe_error_body(404, &response.body)
+    );
+    DeleteOutcome::AlreadyGone
+}
+
+/// Send the request and retry it by the policy, see [`RetryPolicy::retry()`]
+/// 503 is also retried since Twitter returns it during the outages and the maintenance,
+/// other HTTP status errors(e.g. 404 or 429) are returned as they are without retry
+/// * executor: it sends the request
+/// * retry_policy: how many times and how long to wait before each retry
+/// * build_request: build the request, it is called on each attempt(e.g. for signing it again)
+fn call_with_retry(
+    executor: &dyn HttpExecutor,
+    retry_policy: &RetryPolicy,
+    build_request: impl Fn() -> HttpRequest,
+) -> Result<HttpResponse, HttpError> {
+    retry_policy.retry(|| executor.call(&build_request()))
+}
+
+/// Build the error with HTTP status and the error message in the response body
+/// * summary: what was failed e.g. "Failed to delete."
+/// * error: error returned by the request
+fn describe_error(summary: &str, error: HttpError) -> anyhow::Error {
+    match error {
+        HttpError::Status(code, response) => {
+            anyhow::anyhow!("{} {}", summary, describe_error_body(code, &response.body))
+        }
+        HttpError::Transport(transport) => anyhow::anyhow!("{} {}", summary, transport),
+    }
+}
+
+/// Parse the tweet id, it is a snowflake id in the range of u64
+/// * id: tweet id given by the user
+/// * name: parameter name for the error
+fn parse_tweet_id(id: &str, name: &str) -> Result<u64> {
+    match id.parse::<u64>() {
+        Ok(id) => Ok(id),
+        Err(_) => Err(anyhow::anyhow!("{} is not a tweet id: {:?}", name, id)),
+    }
+}
+
+/// Endpoint of Twitter Ads API
+/// * path: path under the API version e.g. accounts/abc/scheduled_tweets
+fn ads_api_url(path: &str) -> Result<Url> {
+    match Url::parse(ADS_API_URL).and_then(|url| url.join(path)) {
+        Ok(url) => Ok(url),
+        Err(e) => Err(anyhow::anyhow!("Ads API Endpoint is not valid: {}", e)),
+    }
+}
+
+/// [`describe_error()`] for Twitter Ads API
+/// 401, 403 and 404 mean the app or the user has no access to the ads account,
+/// they are explained since the message of the API doesn't tell how to get the access
+/// * summary: what was failed e.g. "Failed to delete."
+/// * error: error returned by the request
+fn describe_ads_error(summary: &str, error: HttpError) -> anyhow::Error {
+    match error {
+        HttpError::Status(code @ (401 | 403 | 404), response) => anyhow::anyhow!(
+            "{} The account has no access to the scheduled tweets. The app must be approved for Twitter Ads API and the user must have the ads account. {}",
+            summary,
+            describe_error_body(code, &response.body)
+        ),
+        error => describe_error(summary, error),
+    }
+}
+
+/// Split the comma separated values, the duplicated and empty ones are removed
+/// * csv: comma separated values given by the user
+/// * known: known values, the unknown ones are warned but kept
+/// * name: parameter name for the warning
+fn normalize_csv(csv: &str, known: &[&str], name: &str) -> Vec<String> {
+    let mut values: Vec<String> = vec![];
+    for value in csv
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        if values.iter().any(|existing| existing == value) {
+            continue;
+        }
+        if !known.contains(&value) {
+            warn!("Unknown {}: {:?}, it is requested as it is", name, value);
+        }
+        values.push(value.to_string());
+    }
+    values
+}
+
+/// Describe the error response body with its status code
+/// The raw body is used if it is not the known error format
+fn describe_error_body(code: u16, body: &str) -> String {
+    let messages = match serde_json::from_str::<ErrorResponse>(body) {
+        Ok(error_response) => error_response.messages(),
+        Err(_) => vec![],
+    };
+    if messages.is_empty() {
+        format!("status: {}, body: {}", code, body.trim())
+    } else {
+        format!("status: {}, message: {}", code, messages.join(", "))
+    }
+}
+
+/// How many times the login prompt is shown for the empty input
+const LOGIN_INPUT_ATTEMPTS: usize = 3;
+
+/// Read the answer of the login prompt e.g. username and PIN, it is trimmed
+/// The prompt is shown again for the empty input, and the login is canceled after [`LOGIN_INPUT_ATTEMPTS`] or EOF(Ctrl-D)
+/// * input: e.g. stdin
+/// * prompt: message of the prompt
+/// * name: what is asked for the messages e.g. PIN
+fn read_login_input(input: &mut impl BufRead, prompt: &str, name: &str) -> Result<String> {
+    for attempt in 1..=LOGIN_INPUT_ATTEMPTS {
+        info!("{}", prompt);
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Err(anyhow::anyhow!(
+                "Login was canceled, stdin was closed before the {} was given.",
+                name
+            ));
+        }
+        let line = line.trim();
+        if !line.is_empty() {
+            return Ok(line.to_string());
+        }
+        warn!(
+            "The {} is empty, please input it again. ({} / {})",
+            name, attempt, LOGIN_INPUT_ATTEMPTS
+        );
+    }
+    Err(anyhow::anyhow!(
+        "Login was canceled, the {} was empty {} times.",
+        name,
+        LOGIN_INPUT_ATTEMPTS
+    ))
+}
+
+/// Get the authorization code from the redirected URL
+/// Raw code is also accepted, in that case `state` can't be verified
+/// * input: redirected URL or authorization code
+/// * state: the value sent in the authorization request
+fn parse_oauth2_code(input: &str, state: &str) -> Result<String> {
+    let redirected = match Url::parse(input) {
+        Ok(url) => url,
+        Err(_) => return Ok(input.to_string()),
+    };
+    let params: HashMap<String, String> = redirected.query_pairs().into_owned().collect();
+    if let Some(error) = params.get("error") {
+        return Err(anyhow::anyhow!("Authorization was failed: {}", error));
+    }
+    if params.get("state").map(String::as_str) != Some(state) {
+        return Err(anyhow::anyhow!(
+            "State is not matched, please retry the login"
+        ));
+    }
+    match params.get("code") {
+        Some(code) => Ok(code.to_string()),
+        None => Err(anyhow::anyhow!("No code is found")),
+    }
+}
+
+/// Generate nonce and timestamp for [`build_oauth_signature()`]
+/// Nonce is random for each request, and timestamp is the current UNIX epoch seconds
+fn generate_nonce_and_timestamp() -> (String, String) {
+    let oauth_nonce = Uuid::new_v4().to_string();
+    let oauth_timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .unwrap()
+        .unwrap_or_default()
         .as_secs()
         .to_string();
+    (oauth_nonce, oauth_timestamp)
+}
+
+/// Build OAuth(1.0a) Signature value
+/// Encode, sort, join and such required process will be handled, and this will return the value of authorization header
+/// It is a pure function, nonce and timestamp are given by the caller e.g. [`generate_nonce_and_timestamp()`]
+///
+/// You can use returned value as `Authorization` value in the header
+#[allow(clippy::too_many_arguments)]
+fn build_oauth_signature(
+    oauth_token: &str,
+    oauth_token_secret: &str,
+    consumer_key: &str,
+    consumer_secret: &str,
+    target_endpoint: Url,
+    request_method: &String,
+    query_params: Vec<QueryParam>,
+    oauth_nonce: &str,
+    oauth_timestamp: &str,
+) -> String {
+    // Prepare required params other than arguments
+    let oauth_signature_method = "HMAC-SHA1";
+    let oauth_version = "1.0";
 
     // sorted keys are required, that's why BTreeMap instead of HashMap
-    let mut sorted_sig_param_map: BTreeMap<&str, &str> = BTreeMap::new();
-    sorted_sig_param_map.insert("oauth_consumer_key", consumer_key);
-    sorted_sig_param_map.insert("oauth_token", oauth_token);
-    sorted_sig_param_map.insert("oauth_signature_method", oauth_signature_method);
-    sorted_sig_param_map.insert("oauth_version", oauth_version);
-    sorted_sig_param_map.insert("oauth_nonce", oauth_nonce);
-    sorted_sig_param_map.insert("oauth_timestamp", oauth_timestamp);
+    // keys and values are sorted after the encoding
+    let mut sorted_sig_param_map: BTreeMap<String, String> = BTreeMap::new();
+    for (key, value) in [
+        ("oauth_consumer_key", consumer_key),
+        ("oauth_token", oauth_token),
+        ("oauth_signature_method", oauth_signature_method),
+        ("oauth_version", oauth_version),
+        ("oauth_nonce", oauth_nonce),
+        ("oauth_timestamp", oauth_timestamp),
+    ] {
+        sorted_sig_param_map.insert(percent_encode(key), percent_encode(value));
+    }
 
     // query is also need to add in here, so retrieve all params and insert in there
     for each in &query_params {
-        sorted_sig_param_map.insert(&each.key, &each.encoded_value);
+        sorted_sig_param_map.insert(percent_encode(&each.key), each.encoded_value.clone());
     }
 
     let mut signature_data = String::new();
@@ -536,17 +1944,14 @@ fn build_oauth_signature(
         }
     }
 
-    // https://rust-lang-nursery.github.io/rust-cookbook/encoding/strings.html#percent-encode-a-string
-    let encoded_consumer_secret: String =
-        url::form_urlencoded::byte_serialize(consumer_secret.as_bytes()).collect();
-    let encoded_oauth_token_secret: String =
-        url::form_urlencoded::byte_serialize(oauth_token_secret.as_bytes()).collect();
-    let signagure_key = format!("{}&{}", encoded_consumer_secret, encoded_oauth_token_secret);
+    let signagure_key = format!(
+        "{}&{}",
+        percent_encode(consumer_secret),
+        percent_encode(oauth_token_secret)
+    );
 
-    let encoded_request_target: String =
-        url::form_urlencoded::byte_serialize(target_endpoint.as_str().as_bytes()).collect();
-    let encoded_sigature_data: String =
-        url::form_urlencoded::byte_serialize(signature_data.as_bytes()).collect();
+    let encoded_request_target = percent_encode(target_endpoint.as_str());
+    let encoded_sigature_data = percent_encode(&signature_data);
     let joined_signature_data = format!(
         "{}&{}&{}",
         request_method, encoded_request_target, encoded_sigature_data
@@ -555,16 +1960,29 @@ fn build_oauth_signature(
     let hmac_digest =
         hmacsha1::hmac_sha1(signagure_key.as_bytes(), joined_signature_data.as_bytes());
     let signature = base64::encode(hmac_digest);
-    let encoded_signature: String =
-        url::form_urlencoded::byte_serialize(signature.as_str().as_bytes()).collect();
 
     // Authorization header will use this value, sorted keys are required in here as well
     let oauth_sig = format!(
         "OAuth oauth_consumer_key={},oauth_nonce={},oauth_signature={},oauth_signature_method={},oauth_timestamp={},oauth_token={},oauth_version={}",
-        consumer_key, oauth_nonce, encoded_signature, oauth_signature_method, oauth_timestamp, oauth_token, oauth_version);
+        percent_encode(consumer_key), percent_encode(oauth_nonce), percent_encode(&signature), oauth_signature_method, percent_encode(oauth_timestamp), percent_encode(oauth_token), oauth_version);
     oauth_sig
 }
 
+/// Characters to be encoded in OAuth 1.0a, everything except the unreserved characters of RFC 3986
+/// i.e. `A-Z`, `a-z`, `0-9`, `-`, `.`, `_` and `~` are kept
+/// ref: <https://www.rfc-editor.org/rfc/rfc5849#section-3.6>
+const OAUTH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Percent-encode the value for OAuth 1.0a
+/// Unlike `application/x-www-form-urlencoded`, space is encoded as `%20` instead of `+`
+fn percent_encode(value: &str) -> String {
+    utf8_percent_encode(value, OAUTH_ENCODE_SET).to_string()
+}
+
 /// Query Param Package
 /// This is convenient struct for handling raw param and encoded param
 /// Encoded param is intended for oauth sigature data
@@ -582,8 +2000,7 @@ impl QueryParam {
     /// * key:  Query parameter key
     /// * value: Query parameter value
     fn new(key: &str, value: &str) -> Self {
-        let encoded_value: String =
-            url::form_urlencoded::byte_serialize(value.as_bytes()).collect();
+        let encoded_value = percent_encode(value);
 
         QueryParam {
             key: key.to_string(),
@@ -592,3 +2009,843 @@ impl QueryParam {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use url::Url;
+
+    use crate::http::{HttpError, HttpExecutor, HttpRequest, HttpResponse};
+    use crate::retry::RetryPolicy;
+    use crate::twitter_client::{
+        build_oauth_signature, call_with_retry, describe_error_body, is_browser_available,
+        parse_oauth2_code, percent_encode, read_login_input, AgentOptions, ApiVersion,
+        DeleteOutcome, QueryParam, RateLimit, TwitterAppUserCredential, TwitterClient,
+        TwitterClientTrait, TwitterOAuth2Credential, TwitterRequestToken, UnlikeOutcome,
+        UNBOOKMARK_SCOPES,
+    };
+
+    /// Executor which returns the canned responses in order and records the sent requests
+    struct FakeExecutor {
+        responses: Mutex<VecDeque<Result<HttpResponse, HttpError>>>,
+        requests: Arc<Mutex<Vec<HttpRequest>>>,
+    }
+
+    impl FakeExecutor {
+        fn new(responses: Vec<Result<HttpResponse, HttpError>>) -> Self {
+            FakeExecutor {
+                responses: Mutex::new(responses.into()),
+                requests: Arc::new(Mutex::new(vec![])),
+            }
+        }
+    }
+
+    impl HttpExecutor for FakeExecutor {
+        fn call(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+            self.requests.lock().unwrap().push(request.clone());
+            self.responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("no more canned responses")
+        }
+    }
+
+    fn response(status: u16, headers: &[(&str, &str)], body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Retry without waiting
+    fn no_delay(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Logged-in client which sends the requests to the fake executor
+    fn fake_client(executor: FakeExecutor) -> TwitterClient {
+        let user_cred = TwitterAppUserCredential {
+            username: "dta4hana".to_string(),
+            id: "1234".to_string(),
+            oauth_token: "oauth_token".to_string(),
+            oauth_token_secret: "oauth_token_secret".to_string(),
+            oauth2: None,
+        };
+        new_client(&AgentOptions::default())
+            .unwrap()
+            .init_user_cred(user_cred)
+            .unwrap()
+            .with_executor(executor)
+    }
+
+    /// Build the signature with the credentials of the example in Twitter docs
+    /// ref: <https://developer.twitter.com/en/docs/authentication/oauth-1-0a/creating-a-signature>
+    fn build_example_signature(method: &str, url: &str, query_params: Vec<QueryParam>) -> String {
+        build_oauth_signature(
+            "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb",
+            "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE",
+            "xvz1evFS4wEEPTGEFPHBog",
+            "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+            Url::parse(url).unwrap(),
+            &method.to_string(),
+            query_params,
+            "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg",
+            "1318622958",
+        )
+    }
+
+    #[test]
+    fn build_oauth_signature_of_example() {
+        let signature = build_example_signature(
+            "POST",
+            "https://api.twitter.com/1.1/statuses/update.json",
+            vec![QueryParam::new("include_entities", "true")],
+        );
+        assert_eq!(
+            signature,
+            "OAuth oauth_consumer_key=xvz1evFS4wEEPTGEFPHBog,\
+             oauth_nonce=kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg,\
+             oauth_signature=swB2%2FK4QtoSNF7fQfLzyNivuoj4%3D,\
+             oauth_signature_method=HMAC-SHA1,\
+             oauth_timestamp=1318622958,\
+             oauth_token=370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb,\
+             oauth_version=1.0"
+        );
+    }
+
+    #[test]
+    fn build_oauth_signature_with_spaces_and_reserved_chars() {
+        // the example in Twitter docs, its signature is hCtSmYh+iHYCEqBWrE7C7hYmtUk=
+        let signature = build_example_signature(
+            "POST",
+            "https://api.twitter.com/1.1/statuses/update.json",
+            vec![
+                QueryParam::new("include_entities", "true"),
+                QueryParam::new(
+                    "status",
+                    "Hello Ladies + Gentlemen, a signed OAuth request!",
+                ),
+            ],
+        );
+        assert!(signature.contains(",oauth_signature=hCtSmYh%2BiHYCEqBWrE7C7hYmtUk%3D,"));
+    }
+
+    #[test]
+    fn percent_encode_with_rfc3986() {
+        assert_eq!(
+            percent_encode("Ladies + Gentlemen"),
+            "Ladies%20%2B%20Gentlemen"
+        );
+        assert_eq!(
+            percent_encode("An encoded string!"),
+            "An%20encoded%20string%21"
+        );
+        assert_eq!(percent_encode("-._~"), "-._~");
+        assert_eq!(percent_encode("a=b&c*d/e:f"), "a%3Db%26c%2Ad%2Fe%3Af");
+        assert_eq!(percent_encode("☃"), "%E2%98%83");
+    }
+
+    #[test]
+    fn build_oauth_signature_with_unsorted_params() {
+        // parameters are sorted in the signature base string regardless of the given order
+        let signature = build_example_signature(
+            "GET",
+            "https://api.twitter.com/2/users/1/tweets",
+            vec![
+                QueryParam::new("tweet.fields", "created_at,public_metrics"),
+                QueryParam::new("max_results", "100"),
+            ],
+        );
+        assert!(signature.contains(",oauth_signature=r0I9BHFYcugElJyGqmXSMMLtq5Q%3D,"));
+    }
+
+    fn new_client(agent_options: &AgentOptions) -> anyhow::Result<TwitterClient> {
+        TwitterClient::new(
+            "api_key".to_string(),
+            "consumer_key".to_string(),
+            "consumer_secret".to_string(),
+            None,
+            agent_options,
+        )
+    }
+
+    #[test]
+    fn new_client_with_proxy() {
+        assert!(new_client(&AgentOptions::default()).is_ok());
+
+        let agent_options = AgentOptions {
+            proxy: Some("socks5://127.0.0.1:1080".to_string()),
+            ..Default::default()
+        };
+        assert!(new_client(&agent_options).is_ok());
+
+        let agent_options = AgentOptions {
+            proxy: Some("ftp://127.0.0.1:1080".to_string()),
+            ..Default::default()
+        };
+        assert!(new_client(&agent_options).is_err());
+    }
+
+    #[test]
+    fn default_user_agent_with_version() {
+        let agent_options = AgentOptions::default();
+        assert_eq!(
+            agent_options.user_agent,
+            format!("dta4hana/{}", env!("CARGO_PKG_VERSION"))
+        );
+        let agent_options = AgentOptions {
+            user_agent: "my-tool/1.0".to_string(),
+            ..Default::default()
+        };
+        assert!(new_client(&agent_options).is_ok());
+    }
+
+    #[test]
+    fn describe_error_body_of_apis() {
+        let v1 = r#"{"errors":[{"code":144,"message":"No status found with that ID."}]}"#;
+        assert_eq!(
+            describe_error_body(404, v1),
+            "status: 404, message: No status found with that ID. (code: 144)"
+        );
+
+        let v2 =
+            r#"{"title":"Unauthorized","type":"about:blank","status":401,"detail":"Unauthorized"}"#;
+        assert_eq!(
+            describe_error_body(401, v2),
+            "status: 401, message: Unauthorized: Unauthorized"
+        );
+
+        assert_eq!(
+            describe_error_body(503, "Service Unavailable\n"),
+            "status: 503, body: Service Unavailable"
+        );
+    }
+
+    #[test]
+    fn read_login_input_until_not_empty() {
+        let mut input = "\n  \n dta4hana \n".as_bytes();
+        let username = read_login_input(&mut input, "username:", "username");
+        assert_eq!(username.unwrap(), "dta4hana");
+
+        // 空の入力が続けば中断する
+        let mut input = "\n\n\n1234\n".as_bytes();
+        let error = read_login_input(&mut input, "PIN:", "PIN").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Login was canceled, the PIN was empty 3 times."
+        );
+
+        // EOF(Ctrl-D)は空の値で進めずに中断する
+        let mut input = "\n".as_bytes();
+        let error = read_login_input(&mut input, "PIN:", "PIN").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Login was canceled, stdin was closed before the PIN was given."
+        );
+    }
+
+    #[test]
+    fn parse_oauth2_code_from_redirected_url() {
+        let code = parse_oauth2_code("http://127.0.0.1/callback?state=abc&code=xyz", "abc");
+        assert_eq!(code.unwrap(), "xyz");
+
+        let code = parse_oauth2_code("xyz", "abc");
+        assert_eq!(code.unwrap(), "xyz");
+    }
+
+    #[test]
+    fn parse_oauth2_code_with_invalid_state() {
+        let code = parse_oauth2_code("http://127.0.0.1/callback?state=other&code=xyz", "abc");
+        assert!(code.is_err());
+
+        let code = parse_oauth2_code("http://127.0.0.1/callback?error=access_denied", "abc");
+        assert!(code.is_err());
+    }
+
+    #[test]
+    fn call_with_retry_on_network_errors() {
+        let executor = FakeExecutor::new(vec![
+            Err(HttpError::Transport("Connection refused".to_string())),
+            Err(HttpError::Transport("Connection refused".to_string())),
+            Err(HttpError::Transport("Connection refused".to_string())),
+        ]);
+        let url = Url::parse("http://127.0.0.1:1/").unwrap();
+        let result = call_with_retry(&executor, &no_delay(2), || {
+            HttpRequest::new("GET", url.clone())
+        });
+        assert!(matches!(result, Err(HttpError::Transport(_))));
+        assert_eq!(executor.requests.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn call_with_retry_not_on_status_errors() {
+        let executor = FakeExecutor::new(vec![
+            Err(HttpError::Transport("Connection reset".to_string())),
+            Err(HttpError::Status(404, response(404, &[], "Not Found"))),
+        ]);
+        let url = Url::parse("http://127.0.0.1:1/").unwrap();
+        let result = call_with_retry(&executor, &no_delay(2), || {
+            HttpRequest::new("GET", url.clone())
+        });
+        assert!(matches!(result, Err(HttpError::Status(404, _))));
+        assert_eq!(executor.requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn call_with_retry_on_service_unavailable() {
+        let executor = FakeExecutor::new(vec![
+            Err(HttpError::Status(
+                503,
+                response(503, &[], "Service Unavailable"),
+            )),
+            Err(HttpError::Status(
+                503,
+                response(503, &[], "Service Unavailable"),
+            )),
+            Ok(response(200, &[], "{}")),
+        ]);
+        let url = Url::parse("http://127.0.0.1:1/").unwrap();
+        let result = call_with_retry(&executor, &no_delay(2), || {
+            HttpRequest::new("GET", url.clone())
+        });
+        assert_eq!(result.unwrap().status, 200);
+        assert_eq!(executor.requests.lock().unwrap().len(), 3);
+
+        // リトライ上限を超えたら503をそのまま返す
+        let executor = FakeExecutor::new(vec![
+            Err(HttpError::Status(
+                503,
+                response(503, &[], "Service Unavailable"),
+            )),
+            Err(HttpError::Status(
+                503,
+                response(503, &[], "Service Unavailable"),
+            )),
+        ]);
+        let result = call_with_retry(&executor, &no_delay(1), || {
+            HttpRequest::new("GET", url.clone())
+        });
+        assert!(matches!(result, Err(HttpError::Status(503, _))));
+        assert_eq!(executor.requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn login_with_retry_on_network_errors() {
+        let reset = || Err(HttpError::Transport("Connection reset".to_string()));
+        let executor = FakeExecutor::new(vec![
+            reset(),
+            Ok(response(
+                200,
+                &[],
+                r#"{"data":{"id":"1234","name":"dta4hana","username":"dta4hana"}}"#,
+            )),
+            reset(),
+            Ok(response(
+                200,
+                &[],
+                "oauth_token=request_token&oauth_token_secret=secret&oauth_callback_confirmed=true",
+            )),
+            reset(),
+            Ok(response(
+                200,
+                &[],
+                "oauth_token=access_token&oauth_token_secret=access_secret&user_id=1234&screen_name=dta4hana",
+            )),
+        ]);
+        let requests = executor.requests.clone();
+        let agent_options = AgentOptions {
+            retry_policy: no_delay(1),
+            ..Default::default()
+        };
+        let client = new_client(&agent_options).unwrap().with_executor(executor);
+
+        let request_token = client.request_login("dta4hana").unwrap();
+        assert_eq!(request_token.oauth_token, "request_token");
+        let user_cred = client.complete_login(&request_token, "0000").unwrap();
+        assert_eq!(user_cred.id, "1234");
+        assert_eq!(user_cred.oauth_token, "access_token");
+        assert_eq!(requests.lock().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn login_failure_names_the_phase() {
+        let reset = || Err(HttpError::Transport("Connection reset".to_string()));
+        let agent_options = AgentOptions {
+            retry_policy: no_delay(1),
+            ..Default::default()
+        };
+
+        let client = new_client(&agent_options)
+            .unwrap()
+            .with_executor(FakeExecutor::new(vec![reset(), reset()]));
+        let error = client.request_login("dta4hana").unwrap_err();
+        assert!(error.to_string().contains("liveness check"), "{}", error);
+
+        let client = new_client(&agent_options)
+            .unwrap()
+            .with_executor(FakeExecutor::new(vec![
+                Ok(response(
+                    200,
+                    &[],
+                    r#"{"data":{"id":"1234","name":"dta4hana","username":"dta4hana"}}"#,
+                )),
+                reset(),
+                reset(),
+            ]));
+        let error = client.request_login("dta4hana").unwrap_err();
+        assert!(error.to_string().contains("request token"), "{}", error);
+
+        // 使用済みのPINはリトライしない
+        let executor = FakeExecutor::new(vec![Err(HttpError::Status(
+            401,
+            response(401, &[], "Invalid request token."),
+        ))]);
+        let requests = executor.requests.clone();
+        let client = new_client(&agent_options).unwrap().with_executor(executor);
+        let request_token = TwitterRequestToken {
+            username: "dta4hana".to_string(),
+            id: "1234".to_string(),
+            oauth_token: "request_token".to_string(),
+            authorize_url: String::new(),
+        };
+        let error = client.complete_login(&request_token, "0000").unwrap_err();
+        assert!(error.to_string().contains("access token"), "{}", error);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn fetch_timeline_page_with_canned_response() {
+        let body = r#"{"data":[{"id":"1","created_at":"2022-01-02T03:04:05.000Z","text":"hello",
+            "public_metrics":{"retweet_count":0,"reply_count":0,"like_count":0,"quote_count":0}}],
+            "meta":{"next_token":"next"}}"#;
+        let executor = FakeExecutor::new(vec![Ok(response(
+            200,
+            &[
+                ("x-rate-limit-limit", "900"),
+                ("x-rate-limit-remaining", "899"),
+                ("x-rate-limit-reset", "1672531200"),
+            ],
+            body,
+        ))]);
+        let requests = executor.requests.clone();
+        let tw_client = fake_client(executor);
+
+        let page = tw_client
+            .fetch_timeline_page(
+                Some("2022-01-01".to_string()),
+                None,
+                Some("token".to_string()),
+            )
+            .unwrap();
+        assert_eq!(page.data.len(), 1);
+        assert_eq!(page.data[0].id, "1");
+        assert_eq!(page.meta.next_token, Some("next".to_string()));
+        assert_eq!(
+            tw_client.rate_limit(),
+            Some(RateLimit {
+                remaining: 899,
+                reset: 1672531200,
+                limit: Some(900),
+            })
+        );
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].url.path(), "/2/users/1234/tweets");
+        assert!(requests[0]
+            .query
+            .contains(&("pagination_token".to_string(), "token".to_string())));
+        assert!(requests[0]
+            .query
+            .contains(&("start_time".to_string(), "2022-01-01T00:00:00Z".to_string())));
+        assert!(requests[0]
+            .headers
+            .iter()
+            .any(|(name, value)| name == "Authorization" && value.starts_with("OAuth ")));
+    }
+
+    #[test]
+    fn delete_liked_already_gone() {
+        let executor = FakeExecutor::new(vec![
+            Ok(response(200, &[], "{}")),
+            Err(HttpError::Status(
+                404,
+                response(
+                    404,
+                    &[],
+                    r#"{"errors":[{"code":144,"message":"No status found with that ID."}]}"#,
+                ),
+            )),
+            Err(HttpError::Status(403, response(403, &[], "Forbidden"))),
+        ]);
+        let tw_client = fake_client(executor);
+
+        assert_eq!(tw_client.delete_liked("1").unwrap(), UnlikeOutcome::Unliked);
+        assert_eq!(
+            tw_client.delete_liked("2").unwrap(),
+            UnlikeOutcome::AlreadyGone
+        );
+        assert!(tw_client.delete_liked("3").is_err());
+    }
+
+    #[test]
+    fn get_me_with_user_fields() {
+        let body = r#"{"data":{"id":"1234","name":"DTA4HANA","username":"dta4hana","protected":true,
+            "pinned_tweet_id":"5678"}}"#;
+        let executor = FakeExecutor::new(vec![Ok(response(200, &[], body))]);
+        let requests = executor.requests.clone();
+        let tw_client = fake_client(executor);
+
+        let me = tw_client.get_me().unwrap();
+        assert_eq!(me.id, "1234");
+        assert!(me.protected);
+        assert!(!me.verified);
+        assert_eq!(me.pinned_tweet_id, Some("5678".to_string()));
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].url.path(), "/2/users/me");
+        assert!(requests[0].query.contains(&(
+            "user.fields".to_string(),
+            "verified,protected,pinned_tweet_id".to_string()
+        )));
+    }
+
+    #[test]
+    fn fetch_timeline_page_with_fields_and_expansions() {
+        let body = r#"{"data":[{"id":"1","created_at":"2022-01-02T03:04:05.000Z","lang":"ja","conversation_id":"1",
+            "attachments":{"media_keys":["3_1"]}}],
+            "includes":{"media":[{"media_key":"3_1","type":"photo","url":"https://pbs.twimg.com/media/1.jpg"}]},
+            "meta":{}}"#;
+        let executor = FakeExecutor::new(vec![Ok(response(200, &[], body))]);
+        let requests = executor.requests.clone();
+        let tw_client = fake_client(executor)
+            .with_tweet_fields("lang, attachments,lang,")
+            .with_expansions("attachments.media_keys");
+
+        let page = tw_client.fetch_timeline_page(None, None, None).unwrap();
+        assert_eq!(page.data[0].lang.as_deref(), Some("ja"));
+        assert_eq!(page.data[0].extra["conversation_id"], "1");
+        assert_eq!(page.data[0].public_metrics.like_count, 0);
+        assert_eq!(page.includes.unwrap().media[0].media_key, "3_1");
+
+        let query = &requests.lock().unwrap()[0].query;
+        let param = |key: &str| {
+            query
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str())
+        };
+        // created_at is required for the period filters
+        assert_eq!(param("tweet.fields"), Some("created_at,lang,attachments"));
+        assert_eq!(param("expansions"), Some("attachments.media_keys"));
+        assert_eq!(
+            param("media.fields"),
+            Some("url,preview_image_url,variants")
+        );
+    }
+
+    #[test]
+    fn fetch_timeline_page_of_another_user() {
+        let executor = FakeExecutor::new(vec![Ok(response(200, &[], r#"{"meta":{}}"#))]);
+        let requests = executor.requests.clone();
+        let tw_client = fake_client(executor).with_timeline_user("5678");
+
+        let page = tw_client.fetch_timeline_page(None, None, None).unwrap();
+        assert!(page.data.is_empty());
+        assert_eq!(
+            requests.lock().unwrap()[0].url.path(),
+            "/2/users/5678/tweets"
+        );
+    }
+
+    #[test]
+    fn fetch_timeline_page_with_id_range() {
+        let executor = FakeExecutor::new(vec![Ok(response(200, &[], r#"{"meta":{}}"#))]);
+        let requests = executor.requests.clone();
+        let tw_client = fake_client(executor)
+            .with_id_range(Some("100"), Some("200"))
+            .unwrap();
+
+        tw_client.fetch_timeline_page(None, None, None).unwrap();
+        let query = requests.lock().unwrap()[0].query.clone();
+        // IDは両端を含むため, APIの排他的な境界に変換される
+        assert!(query.contains(&("since_id".to_string(), "99".to_string())));
+        assert!(query.contains(&("until_id".to_string(), "201".to_string())));
+
+        let error = tw_client
+            .fetch_timeline_page(Some("2022-01-01".to_string()), None, None)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The dates(since/until) and the ids(since_id/until_id) can't be used together"
+        );
+        assert!(fake_client(FakeExecutor::new(vec![]))
+            .with_id_range(Some("abc"), None)
+            .is_err());
+        assert!(fake_client(FakeExecutor::new(vec![]))
+            .with_id_range(Some("200"), Some("100"))
+            .is_err());
+    }
+
+    #[test]
+    fn fetch_pages_with_page_size() {
+        let executor = FakeExecutor::new(vec![
+            Ok(response(200, &[], r#"{"meta":{}}"#)),
+            Ok(response(200, &[], r#"{"meta":{}}"#)),
+        ]);
+        let requests = executor.requests.clone();
+        let tw_client = fake_client(executor).with_page_size(5).unwrap();
+
+        tw_client.fetch_timeline_page(None, None, None).unwrap();
+        tw_client.fetch_likes_page(None).unwrap();
+        let requests = requests.lock().unwrap();
+        let max_results = |request: &HttpRequest| {
+            request
+                .query
+                .iter()
+                .find(|(key, _)| key == "max_results")
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(max_results(&requests[0]), Some("5".to_string()));
+        // likesの下限は10
+        assert_eq!(max_results(&requests[1]), Some("10".to_string()));
+
+        assert!(fake_client(FakeExecutor::new(vec![]))
+            .with_page_size(4)
+            .is_err());
+        assert!(fake_client(FakeExecutor::new(vec![]))
+            .with_page_size(101)
+            .is_err());
+    }
+
+    #[test]
+    fn wait_page_interval_with_fake_sleep() {
+        let mut slept = vec![];
+        fake_client(FakeExecutor::new(vec![]))
+            .with_page_interval(Duration::from_millis(100))
+            .wait_page_interval_with_sleep(|delay| slept.push(delay));
+        assert_eq!(slept, vec![Duration::from_millis(100)]);
+
+        // インターバルがなければ待たない
+        let mut slept = vec![];
+        fake_client(FakeExecutor::new(vec![]))
+            .wait_page_interval_with_sleep(|delay| slept.push(delay));
+        assert!(slept.is_empty());
+    }
+
+    #[test]
+    fn browser_availability_of_sessions() {
+        let env = |names: &'static [&'static str]| move |name: &str| names.contains(&name);
+        assert!(!is_browser_available(env(&["SSH_CONNECTION"])));
+        assert!(is_browser_available(env(&["SSH_CONNECTION", "DISPLAY"])));
+        if cfg!(target_os = "linux") {
+            assert!(!is_browser_available(env(&[])));
+            assert!(is_browser_available(env(&["WAYLAND_DISPLAY"])));
+            assert!(is_browser_available(env(&["BROWSER"])));
+        }
+    }
+
+    #[test]
+    fn require_oauth2_scopes_of_credential() {
+        let client_with_scope = |scope: Option<&str>| {
+            let user_cred = TwitterAppUserCredential {
+                username: "dta4hana".to_string(),
+                id: "1234".to_string(),
+                oauth_token: "oauth_token".to_string(),
+                oauth_token_secret: "oauth_token_secret".to_string(),
+                oauth2: Some(TwitterOAuth2Credential {
+                    access_token: "access_token".to_string(),
+                    refresh_token: None,
+                    scope: scope.map(str::to_string),
+                }),
+            };
+            new_client(&AgentOptions::default())
+                .unwrap()
+                .init_user_cred(user_cred)
+                .unwrap()
+        };
+        let tw_client =
+            client_with_scope(Some("tweet.read users.read bookmark.read bookmark.write"));
+        assert!(tw_client.require_oauth2_scopes(UNBOOKMARK_SCOPES).is_ok());
+
+        let tw_client = client_with_scope(Some("tweet.read users.read"));
+        let error = tw_client
+            .require_oauth2_scopes(UNBOOKMARK_SCOPES)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "This action needs the bookmark.read bookmark.write scope, re-login with `login --oauth2 --scopes \"tweet.read users.read bookmark.read bookmark.write\"`."
+        );
+
+        // スコープが記録されていない古い認証情報はチェックしない
+        let tw_client = client_with_scope(None);
+        assert!(tw_client.require_oauth2_scopes(UNBOOKMARK_SCOPES).is_ok());
+        assert!(fake_client(FakeExecutor::new(vec![]))
+            .require_oauth2_scopes(UNBOOKMARK_SCOPES)
+            .is_err());
+    }
+
+    #[test]
+    fn delete_tweet_with_v2() {
+        let executor = FakeExecutor::new(vec![
+            Ok(response(200, &[], r#"{"data":{"deleted":true}}"#)),
+            Ok(response(200, &[], r#"{"data":{"deleted":false}}"#)),
+            Err(HttpError::Status(404, response(404, &[], "Not Found"))),
+        ]);
+        let requests = executor.requests.clone();
+        let user_cred = TwitterAppUserCredential {
+            username: "dta4hana".to_string(),
+            id: "1234".to_string(),
+            oauth_token: "oauth_token".to_string(),
+            oauth_token_secret: "oauth_token_secret".to_string(),
+            oauth2: Some(TwitterOAuth2Credential {
+                access_token: "access_token".to_string(),
+                refresh_token: None,
+                scope: None,
+            }),
+        };
+        let tw_client = new_client(&AgentOptions::default())
+            .unwrap()
+            .init_user_cred(user_cred)
+            .unwrap()
+            .with_executor(executor);
+        // OAuth 2.0の認証情報があっても, 指定しなければv1.1で削除する
+        assert_eq!(tw_client.api_version(), ApiVersion::V1);
+        let tw_client = tw_client.with_api_version(ApiVersion::V2);
+
+        tw_client.delete_tweet("1").unwrap();
+        let request = requests.lock().unwrap()[0].clone();
+        assert_eq!(request.method, "DELETE");
+        assert_eq!(request.url.path(), "/2/tweets/1");
+        assert!(request.headers.contains(&(
+            "Authorization".to_string(),
+            "Bearer access_token".to_string()
+        )));
+        assert!(tw_client.delete_tweet("2").is_err());
+        assert_eq!(
+            tw_client.delete_tweet("3").unwrap(),
+            DeleteOutcome::AlreadyGone
+        );
+
+        // OAuth 1.0aだけの場合にv2を指定するとエラー
+        let tw_client = fake_client(FakeExecutor::new(vec![]));
+        let error = tw_client
+            .with_api_version(ApiVersion::V2)
+            .delete_tweet("1")
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("OAuth 2.0 credential is not loaded."));
+    }
+
+    #[test]
+    fn fetch_tweets_by_ids_with_not_found() {
+        let body = r#"{
+            "data": [{"id": "1", "created_at": "2022-01-01T00:00:00.000Z", "text": "still here"}],
+            "errors": [{"resource_id": "2", "type": "https://api.twitter.com/2/problems/resource-not-found"}]
+        }"#;
+        let executor = FakeExecutor::new(vec![Ok(response(200, &[], body))]);
+        let requests = executor.requests.clone();
+        let tw_client = fake_client(executor);
+
+        let ids = vec!["1".to_string(), "2".to_string()];
+        let lookup = tw_client.fetch_tweets_by_ids(&ids).unwrap();
+        assert_eq!(lookup.data.len(), 1);
+        assert!(lookup.errors[0].is_not_found());
+        let query = requests.lock().unwrap()[0].query.clone();
+        assert!(query.contains(&("ids".to_string(), "1,2".to_string())));
+
+        let ids: Vec<String> = (0..101).map(|id| id.to_string()).collect();
+        assert!(tw_client.fetch_tweets_by_ids(&ids).is_err());
+    }
+
+    #[test]
+    fn fetch_timeline_page_with_error_status() {
+        let executor = FakeExecutor::new(vec![Err(HttpError::Status(
+            401,
+            response(401, &[], "Unauthorized"),
+        ))]);
+        let tw_client = fake_client(executor);
+
+        let error = tw_client.fetch_timeline_page(None, None, None).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to fetch tweets. status: 401, body: Unauthorized"
+        );
+        assert_eq!(tw_client.rate_limit(), None);
+    }
+
+    #[test]
+    fn fetch_scheduled_of_ads_account() {
+        let executor = FakeExecutor::new(vec![
+            Ok(response(
+                200,
+                &[],
+                r#"{"data":[{"id":1185284172340523008,"id_str":"1185284172340523008","text":"hello",
+                "scheduled_at":"2030-01-01T00:00:00Z","scheduled_status":"SCHEDULED"}],
+                "next_cursor":"c-1","data_type":"scheduled_tweet"}"#,
+            )),
+            Err(HttpError::Status(
+                403,
+                response(
+                    403,
+                    &[],
+                    r#"{"errors":[{"code":403,"message":"User is not authorized"}]}"#,
+                ),
+            )),
+        ]);
+        let requests = executor.requests.clone();
+        let tw_client = fake_client(executor);
+
+        let page = tw_client.fetch_scheduled("18ce54d4x5t", None).unwrap();
+        assert_eq!(page.data[0].id, "1185284172340523008");
+        assert_eq!(page.next_cursor, Some("c-1".to_string()));
+        assert_eq!(
+            requests.lock().unwrap()[0].url.as_str(),
+            "https://ads-api.twitter.com/12/accounts/18ce54d4x5t/scheduled_tweets"
+        );
+
+        // 広告APIの権限がなければ, その旨を伝える
+        let error = tw_client
+            .fetch_scheduled("18ce54d4x5t", Some("c-1".to_string()))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("The account has no access to the scheduled tweets."));
+    }
+
+    #[test]
+    fn fetch_likes_page_with_pagination_token() {
+        let executor = FakeExecutor::new(vec![Ok(response(
+            200,
+            &[],
+            r#"{"data":[{"id":"1","text":"liked","created_at":"2022-01-01T00:00:00.000Z"}],"meta":{"next_token":"next2"}}"#,
+        ))]);
+        let requests = executor.requests.clone();
+        let tw_client = fake_client(executor);
+
+        let page = tw_client
+            .fetch_likes_page(Some("next1".to_string()))
+            .unwrap();
+        assert_eq!(page.data.len(), 1);
+        assert_eq!(page.meta.next_token, Some("next2".to_string()));
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].url.path(), "/2/users/1234/liked_tweets");
+        assert!(requests[0]
+            .query
+            .contains(&("pagination_token".to_string(), "next1".to_string())));
+    }
+}
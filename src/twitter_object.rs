@@ -1,30 +1,271 @@
 //! Twitter API response object definition
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 /// Wrapper of the response
 /// `T` is depending on the endpoints, but always it will be wrapped with `data`
+/// `meta` is returned only by the endpoints of the lists e.g. bookmarks
 #[derive(Deserialize, Serialize)]
 pub struct ResponseObject<T> {
     pub data: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
-#[derive(Deserialize, Serialize)]
+/// Wrapper of the response of the paginated endpoints e.g. timeline
+/// `data` is omitted if there is no result
+/// `includes` has the expanded objects only if `expansions` is requested
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ResponsePage<T> {
+    #[serde(default = "Vec::new")]
+    pub data: Vec<T>,
+    #[serde(default)]
+    pub meta: Meta,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub includes: Option<Includes>,
+}
+
+/// Wrapper of the response of the lookup endpoints e.g. `GET /2/tweets?ids=`
+/// The ids which couldn't be returned e.g. deleted tweets are in `errors` instead of `data`
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ResponseLookup<T> {
+    #[serde(default = "Vec::new")]
+    pub data: Vec<T>,
+    #[serde(default = "Vec::new")]
+    pub errors: Vec<ResourceError>,
+}
+
+/// Error of a resource in the partial response of v2
+/// e.g. `{"resource_id": "1", "title": "Not Found Error", "type": "https://api.twitter.com/2/problems/resource-not-found"}`
+#[derive(Clone, Deserialize, Debug, Default, Serialize)]
+pub struct ResourceError {
+    pub resource_id: Option<String>,
+    pub value: Option<String>,
+    pub title: Option<String>,
+    pub detail: Option<String>,
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+}
+
+impl ResourceError {
+    /// Id of the resource, `value` is the given parameter if `resource_id` is missing
+    pub fn id(&self) -> Option<&str> {
+        self.resource_id.as_deref().or(self.value.as_deref())
+    }
+
+    /// Whether the resource doesn't exist e.g. the tweet was deleted
+    /// Other errors e.g. the tweet of a protected user don't mean it is gone
+    pub fn is_not_found(&self) -> bool {
+        matches!(&self.error_type, Some(error_type) if error_type.ends_with("/resource-not-found"))
+    }
+}
+
+/// Expanded objects of the response
+/// * media: expanded by `attachments.media_keys`
+/// * tweets: referenced tweets expanded by `referenced_tweets.id`
+#[derive(Deserialize, Debug, Default, Serialize)]
+pub struct Includes {
+    #[serde(default)]
+    pub media: Vec<Media>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tweets: Vec<Tweet>,
+}
+
+impl ResponsePage<Tweet> {
+    /// Fill `author_id` of the referenced tweets from the expanded tweets
+    /// Nothing is filled unless `referenced_tweets.id` and `author_id` are requested
+    pub fn fill_referenced_authors(&mut self) {
+        let includes = match &self.includes {
+            Some(includes) => includes,
+            None => return,
+        };
+        let authors: HashMap<&str, &str> = includes
+            .tweets
+            .iter()
+            .filter_map(|tweet| Some((tweet.id.as_str(), tweet.author_id.as_deref()?)))
+            .collect();
+        for tweet in &mut self.data {
+            for referenced in &mut tweet.referenced_tweets {
+                if let Some(author_id) = authors.get(referenced.id.as_str()) {
+                    referenced.author_id = Some(author_id.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Photo, video or GIF attached to the tweet
+/// * kind: `photo`, `video` or `animated_gif`
+/// * url: URL of the photo, it is None for the videos
+/// * preview_image_url: URL of the thumbnail of the videos
+/// * variants: encodings of the videos and GIFs, empty for the photos
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Media {
+    pub media_key: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub url: Option<String>,
+    pub preview_image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<MediaVariant>,
+}
+
+impl Media {
+    /// URL to download the original media
+    /// It is the photo itself, or the variant of the highest bit rate for the videos and GIFs
+    /// None if the API returned no URL for it
+    pub fn download_url(&self) -> Option<&str> {
+        let variant = self
+            .variants
+            .iter()
+            .filter(|variant| variant.content_type == "video/mp4")
+            .max_by_key(|variant| variant.bit_rate.unwrap_or(0));
+        match variant {
+            Some(variant) => Some(&variant.url),
+            None => self.url.as_deref(),
+        }
+    }
+}
+
+/// Encoding of the video or GIF
+/// * bit_rate: it is omitted for the streaming playlist e.g. `application/x-mpegURL`
+/// * content_type: e.g. `video/mp4`
+/// * url: URL of the file
+#[derive(Deserialize, Debug, Serialize)]
+pub struct MediaVariant {
+    pub bit_rate: Option<u32>,
+    pub content_type: String,
+    pub url: String,
+}
+
+/// Meta of the paginated response
+/// * result_count: how many objects are in this page
+/// * next_token: it is given to get the next page, None if it is the last page
+/// * previous_token: it is given to get the previous page, None if it is the first page
+#[derive(Deserialize, Debug, Default, Serialize)]
+pub struct Meta {
+    #[serde(default)]
+    pub result_count: u32,
+    pub next_token: Option<String>,
+    pub previous_token: Option<String>,
+}
+
+/// Twitter user
+/// `verified`, `protected` and `pinned_tweet_id` are filled only if they are requested with `user.fields`
+/// `pinned_tweet_id` is omitted if the user has no pinned tweet
+#[derive(Deserialize, Debug, Serialize)]
 pub struct User {
     pub id: String,
     pub name: String,
     pub username: String,
+    #[serde(default)]
+    pub verified: bool,
+    #[serde(default)]
+    pub protected: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_tweet_id: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct Tweet {
     pub id: String,
     pub created_at: String,
+    /// Body of the tweet, it may be truncated for retweets e.g. `RT @user: ...…`
+    #[serde(default)]
+    pub text: String,
+    /// It is zero if `public_metrics` is not requested with `tweet.fields`
+    #[serde(default)]
     pub public_metrics: PublicMetrics,
     pub attachments: Option<Attachments>,
+    /// It is set if the tweet is a reply
+    #[serde(default)]
+    pub in_reply_to_user_id: Option<String>,
+    /// Replied, quoted or retweeted tweets
+    #[serde(default)]
+    pub referenced_tweets: Vec<ReferencedTweet>,
+    /// Author of the tweet, it is filled only if `author_id` is requested with `tweet.fields`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_id: Option<String>,
+    /// Client app which posted the tweet e.g. `Twitter for iPhone`
+    /// It is not always present, e.g. `source` is not requested with `tweet.fields` or the API doesn't return it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Language detected by Twitter as BCP 47 e.g. `ja`, it is `und` if it couldn't be detected
+    /// It is filled only if `lang` is requested with `tweet.fields`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Whether the tweet may have sensitive content e.g. flagged as sensitive media
+    /// It is filled only if `possibly_sensitive` is requested with `tweet.fields`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub possibly_sensitive: Option<bool>,
+    /// Countries where the tweet is withheld, it is missing if the tweet is not withheld
+    /// It is filled only if `withheld` is requested with `tweet.fields`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withheld: Option<Withheld>,
+    /// Other fields requested with `tweet.fields` e.g. `lang`, they are kept as they are for the export
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
-/// Will be used for chekcing how many likes, retweets and replies on the tweet
+impl Tweet {
+    /// Whether the tweet is a reply(including the replies to yourself i.e. threads)
+    /// A tweet which quotes and replies at the same time is a reply
+    pub fn is_reply(&self) -> bool {
+        self.in_reply_to_user_id.is_some()
+            || self
+                .referenced_tweets
+                .iter()
+                .any(|referenced| referenced.kind == "replied_to")
+    }
+
+    /// The retweeted tweet if the tweet is a retweet
+    pub fn retweeted(&self) -> Option<&ReferencedTweet> {
+        self.referenced_tweets
+            .iter()
+            .find(|referenced| referenced.kind == "retweeted")
+    }
+
+    /// Whether the tweet has any photos/videos
+    pub fn has_media(&self) -> bool {
+        match &self.attachments {
+            Some(attachments) => !attachments.media_keys.is_empty(),
+            None => false,
+        }
+    }
+
+    /// Whether the tweet is flagged as possibly sensitive, it is false if `possibly_sensitive` is missing
+    pub fn is_sensitive(&self) -> bool {
+        self.possibly_sensitive.unwrap_or(false)
+    }
+
+    /// Whether the tweet is withheld in any country or by copyright, it is false if `withheld` is missing
+    pub fn is_withheld(&self) -> bool {
+        match &self.withheld {
+            Some(withheld) => withheld.copyright || !withheld.country_codes.is_empty(),
+            None => false,
+        }
+    }
+}
+
+/// Withheld details of the tweet
+/// * copyright: withheld by a DMCA notice
+/// * country_codes: ISO 3166-1 alpha-2 codes of the countries where the tweet is withheld e.g. `DE`
+#[derive(Deserialize, Debug, Default, Serialize)]
+pub struct Withheld {
+    #[serde(default)]
+    pub copyright: bool,
+    #[serde(default)]
+    pub country_codes: Vec<String>,
+}
+
+/// Response of `DELETE /2/tweets/:id`
 #[derive(Deserialize, Debug, Serialize)]
+pub struct Deleted {
+    pub deleted: bool,
+}
+
+/// Will be used for chekcing how many likes, retweets and replies on the tweet
+#[derive(Deserialize, Debug, Default, Serialize)]
 pub struct PublicMetrics {
     pub retweet_count: u32,
     pub reply_count: u32,
@@ -32,8 +273,287 @@ pub struct PublicMetrics {
     pub quote_count: u32,
 }
 
+impl PublicMetrics {
+    /// Total of the replies, the retweets and the quotes, i.e. how much the others engaged with the tweet except the likes
+    pub fn interactions(&self) -> u64 {
+        u64::from(self.reply_count) + u64::from(self.retweet_count) + u64::from(self.quote_count)
+    }
+}
+
+/// Referenced tweet of the tweet
+/// * kind: `replied_to`, `quoted` or `retweeted`
+/// * author_id: author of the referenced tweet, it is not in the response but filled by [`ResponsePage::fill_referenced_authors()`]
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ReferencedTweet {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_id: Option<String>,
+}
+
 /// Will be used for chekcing the attachments
+/// `media_keys` can be omitted e.g. the tweet has only a poll
 #[derive(Deserialize, Debug, Serialize)]
 pub struct Attachments {
+    #[serde(default)]
     pub media_keys: Vec<String>,
 }
+
+/// Direct message event
+/// * event_type: `MessageCreate`, `ParticipantsJoin` or `ParticipantsLeave`
+/// * sender_id: the user who sent the message, it is omitted on some event types
+/// * text: body of the message, empty if it is not a message
+#[derive(Deserialize, Debug, Serialize)]
+pub struct DmEvent {
+    pub id: String,
+    pub event_type: String,
+    pub created_at: String,
+    pub sender_id: Option<String>,
+    #[serde(default)]
+    pub text: String,
+}
+
+/// Scheduled tweet of Twitter Ads API, it is not posted yet
+/// * id: `id_str` of the response, the numeric `id` can't be handled by JSON precisely
+/// * text: body of the tweet
+/// * scheduled_at: when it will be posted e.g. 2022-01-01T00:00:00Z
+/// * scheduled_status: e.g. SCHEDULED, IN_PROGRESS
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ScheduledTweet {
+    #[serde(rename = "id_str")]
+    pub id: String,
+    #[serde(default)]
+    pub text: String,
+    pub scheduled_at: Option<String>,
+    pub scheduled_status: Option<String>,
+}
+
+/// Response of the scheduled tweets of Twitter Ads API
+/// It is not the same format as [`ResponsePage`], the next page is `next_cursor`
+#[derive(Deserialize, Debug, Default, Serialize)]
+pub struct ScheduledPage {
+    #[serde(default = "Vec::new")]
+    pub data: Vec<ScheduledTweet>,
+    pub next_cursor: Option<String>,
+}
+
+/// Error response of the APIs
+/// v1.1 has `errors`, and v2 has `title` + `detail`(and sometimes `errors` as well)
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ErrorResponse {
+    pub errors: Option<Vec<ApiError>>,
+    pub title: Option<String>,
+    pub detail: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ApiError {
+    pub code: Option<u32>,
+    pub message: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Human readable messages in the response, empty if there is no message
+    pub fn messages(&self) -> Vec<String> {
+        let mut messages = vec![];
+        for error in self.errors.iter().flatten() {
+            match (&error.message, error.code) {
+                (Some(message), Some(code)) => {
+                    messages.push(format!("{} (code: {})", message, code))
+                }
+                (Some(message), None) => messages.push(message.to_string()),
+                _ => {}
+            }
+        }
+        match (&self.title, &self.detail) {
+            (Some(title), Some(detail)) => messages.push(format!("{}: {}", title, detail)),
+            (None, Some(detail)) => messages.push(detail.to_string()),
+            (Some(title), None) => messages.push(title.to_string()),
+            _ => {}
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::twitter_object::{Media, ResponseLookup, ResponseObject, ResponsePage, Tweet, User};
+
+    #[test]
+    fn deserialize_pages() {
+        let body = r#"{
+            "data": [{
+                "id": "1",
+                "created_at": "2022-01-01T00:00:00.000Z",
+                "public_metrics": {"retweet_count": 0, "reply_count": 0, "like_count": 0, "quote_count": 0}
+            }],
+            "meta": {"result_count": 1, "next_token": "next"}
+        }"#;
+        let page: ResponsePage<Tweet> = serde_json::from_str(body).unwrap();
+        assert_eq!(page.data.len(), 1);
+        assert_eq!(page.meta.result_count, 1);
+        assert_eq!(page.meta.next_token.as_deref(), Some("next"));
+        assert!(page.meta.previous_token.is_none());
+
+        let body = r#"{"meta": {"result_count": 0}}"#;
+        let page: ResponsePage<Tweet> = serde_json::from_str(body).unwrap();
+        assert!(page.data.is_empty());
+        assert!(page.meta.next_token.is_none());
+    }
+
+    #[test]
+    fn deserialize_object_with_meta() {
+        let body = r#"{
+            "data": [],
+            "meta": {"result_count": 0, "previous_token": "prev"}
+        }"#;
+        let object: ResponseObject<Vec<Tweet>> = serde_json::from_str(body).unwrap();
+        let meta = object.meta.unwrap();
+        assert_eq!(meta.result_count, 0);
+        assert!(meta.next_token.is_none());
+        assert_eq!(meta.previous_token.as_deref(), Some("prev"));
+
+        // meta のないエンドポイントもある e.g. users/me
+        let body = r#"{"data": {"id": "1", "name": "name", "username": "user"}}"#;
+        let object: ResponseObject<User> = serde_json::from_str(body).unwrap();
+        assert!(object.meta.is_none());
+    }
+
+    #[test]
+    fn fill_referenced_authors_from_includes() {
+        let body = r#"{
+            "data": [{
+                "id": "1",
+                "created_at": "2022-01-01T00:00:00.000Z",
+                "referenced_tweets": [{"type": "retweeted", "id": "2"}]
+            }],
+            "includes": {"tweets": [{"id": "2", "created_at": "2021-01-01T00:00:00.000Z", "author_id": "10"}]},
+            "meta": {"result_count": 1}
+        }"#;
+        let mut page: ResponsePage<Tweet> = serde_json::from_str(body).unwrap();
+        assert!(page.data[0].retweeted().unwrap().author_id.is_none());
+        page.fill_referenced_authors();
+        assert_eq!(
+            page.data[0].retweeted().unwrap().author_id.as_deref(),
+            Some("10")
+        );
+    }
+
+    #[test]
+    fn deserialize_lookup_with_errors() {
+        let body = r#"{
+            "data": [{"id": "1", "created_at": "2022-01-01T00:00:00.000Z", "text": "still here"}],
+            "errors": [{
+                "value": "2",
+                "detail": "Could not find tweet with ids: [2].",
+                "title": "Not Found Error",
+                "resource_type": "tweet",
+                "parameter": "ids",
+                "resource_id": "2",
+                "type": "https://api.twitter.com/2/problems/resource-not-found"
+            }, {
+                "value": "3",
+                "title": "Authorization Error",
+                "resource_id": "3",
+                "type": "https://api.twitter.com/2/problems/not-authorized-for-resource"
+            }]
+        }"#;
+        let lookup: ResponseLookup<Tweet> = serde_json::from_str(body).unwrap();
+        assert_eq!(lookup.data[0].id, "1");
+        assert_eq!(lookup.errors[0].id(), Some("2"));
+        assert!(lookup.errors[0].is_not_found());
+        assert!(!lookup.errors[1].is_not_found());
+
+        // 全件見つからない場合はdataが省略される
+        let lookup: ResponseLookup<Tweet> = serde_json::from_str(r#"{"errors": []}"#).unwrap();
+        assert!(lookup.data.is_empty());
+    }
+
+    #[test]
+    fn download_url_of_media() {
+        let body = r#"{
+            "media_key": "7_1",
+            "type": "video",
+            "preview_image_url": "https://pbs.twimg.com/thumb/1.jpg",
+            "variants": [
+                {"content_type": "application/x-mpegURL", "url": "https://video.twimg.com/1.m3u8"},
+                {"bit_rate": 256000, "content_type": "video/mp4", "url": "https://video.twimg.com/low.mp4"},
+                {"bit_rate": 2176000, "content_type": "video/mp4", "url": "https://video.twimg.com/high.mp4"}
+            ]
+        }"#;
+        let media: Media = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            media.download_url(),
+            Some("https://video.twimg.com/high.mp4")
+        );
+
+        let body =
+            r#"{"media_key": "3_1", "type": "photo", "url": "https://pbs.twimg.com/media/1.jpg"}"#;
+        let media: Media = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            media.download_url(),
+            Some("https://pbs.twimg.com/media/1.jpg")
+        );
+    }
+
+    #[test]
+    fn deserialize_tweet_without_attachments() {
+        let body = r#"{
+            "id": "1",
+            "created_at": "2022-01-01T00:00:00.000Z",
+            "public_metrics": {"retweet_count": 0, "reply_count": 0, "like_count": 0, "quote_count": 0}
+        }"#;
+        let tweet: Tweet = serde_json::from_str(body).unwrap();
+        assert!(tweet.text.is_empty());
+        assert!(tweet.attachments.is_none());
+        assert!(!tweet.is_reply());
+        assert!(!tweet.has_media());
+    }
+
+    #[test]
+    fn deserialize_reply() {
+        // quote and reply at the same time
+        let body = r#"{
+            "id": "1",
+            "created_at": "2022-01-01T00:00:00.000Z",
+            "public_metrics": {"retweet_count": 0, "reply_count": 0, "like_count": 0, "quote_count": 0},
+            "in_reply_to_user_id": "2",
+            "referenced_tweets": [{"type": "quoted", "id": "3"}, {"type": "replied_to", "id": "4"}]
+        }"#;
+        let tweet: Tweet = serde_json::from_str(body).unwrap();
+        assert!(tweet.is_reply());
+
+        let body = r#"{
+            "id": "1",
+            "created_at": "2022-01-01T00:00:00.000Z",
+            "public_metrics": {"retweet_count": 0, "reply_count": 0, "like_count": 0, "quote_count": 0},
+            "referenced_tweets": [{"type": "quoted", "id": "3"}]
+        }"#;
+        let tweet: Tweet = serde_json::from_str(body).unwrap();
+        assert!(!tweet.is_reply());
+    }
+
+    #[test]
+    fn deserialize_tweet_with_attachments() {
+        let body = r#"{
+            "id": "1",
+            "created_at": "2022-01-01T00:00:00.000Z",
+            "public_metrics": {"retweet_count": 0, "reply_count": 0, "like_count": 0, "quote_count": 0},
+            "text": "photo of HANA",
+            "attachments": {"media_keys": ["3_1"]}
+        }"#;
+        let tweet: Tweet = serde_json::from_str(body).unwrap();
+        assert_eq!(tweet.text, "photo of HANA");
+        assert!(tweet.has_media());
+
+        let body = r#"{
+            "id": "2",
+            "created_at": "2022-01-01T00:00:00.000Z",
+            "public_metrics": {"retweet_count": 0, "reply_count": 0, "like_count": 0, "quote_count": 0},
+            "attachments": {"poll_ids": ["1"]}
+        }"#;
+        let tweet: Tweet = serde_json::from_str(body).unwrap();
+        assert!(!tweet.has_media());
+    }
+}